@@ -1,12 +1,58 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
 use speedy2d::Graphics2D;
 use speedy2d::color::Color;
+use speedy2d::shape::Rectangle;
+use speedy2d::font::{Font, TextLayout, TextOptions};
 
 extern crate nalgebra as na;
 use na::{Dynamic, VecStorage, Matrix};
 
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use rayon::prelude::*;
+
+use crate::ant::{AntProfile, AntState, StepOutcome};
+use crate::colony::{stream_rng, AntColony, AnytimeSolutions};
+use crate::error::AcoError;
+use crate::observer::{NewBestCallback, NoopObserver, SolverObserver};
+use crate::path::Path;
+use crate::recorder::RunRecorder;
+use crate::style::{RenderLayer, RenderStyle};
+use crate::vertex::Vertex;
+
 type MatDyn = Matrix<f32, Dynamic, Dynamic, VecStorage<f32, Dynamic, Dynamic>>;
-pub type VerticeLoc = (usize, usize);
 
+const STRAIGHT_COST: f32 = 1.0;
+const DIAGONAL_COST: f32 = std::f32::consts::SQRT_2;
+/// Floor pheromone never evaporates below, keeps the likelihood math away from zero
+const MIN_PHEROMONE: f32 = 1e-6;
+/// Default cap on the pheromone of an edge, far above anything a normal run lays down but
+/// low enough that raising it to alpha stays finite
+const DEFAULT_MAX_PHEROMONE: f32 = 1e6;
+/// Fraction of the recent edge usage that is left after every decay
+const RECENT_USAGE_RETAINED: f32 = 0.5;
+/// Grids with more vertices than this are too dense to label
+const MAX_LABELLED_VERTICES: usize = 400;
+/// Number of iterations the cost history remembers unless configured otherwise
+const DEFAULT_HISTORY_CAPACITY: usize = 10000;
+/// Random stream the initial pheromone jitter is drawn from, kept apart from the colony streams
+const JITTER_STREAM: u64 = u64::MAX;
+
+/// Most pixels an image may have to be turned into a map. The pheromone of every vertice pair
+/// is held in a dense matrix, this many vertices already take 64 MiB of it.
+#[cfg(feature = "image")]
+pub const MAX_IMAGE_VERTICES: usize = 4096;
+
+/// Pheromone every edge starts out with before init_jitter is applied
+const INITIAL_PHEROMONE: f32 = 1.0;
+pub type VerticeLoc = Vertex;
+
+/// Pheromone storage, edges are undirected so (v0, v1) and (v1, v0) always hold the same value
+#[derive(Clone, PartialEq)]
 struct ACOGraph {
     mat: MatDyn,
     width: usize,
@@ -22,181 +68,4617 @@ impl ACOGraph {
     fn get_edg_value(&self, v0: VerticeLoc, v1: VerticeLoc) -> f32 {
         let row = self.idx(v0);
         let col = self.idx(v1);
-        self.mat[(col, row)]
+        self.mat[(row, col)]
     }
 
     #[allow(dead_code)]
     fn set_edg_value(&mut self, v0: VerticeLoc, v1: VerticeLoc, value: f32) {
         let row = self.idx(v0);
         let col = self.idx(v1);
+        self.mat[(row, col)] = value;
         self.mat[(col, row)] = value;
     }
 
+    /// Set every edge to value
+    pub fn fill(&mut self, value: f32) {
+        self.mat.fill(value);
+    }
+
+    /// Multiply every edge by factor
+    #[allow(dead_code)]
+    pub fn scale(&mut self, factor: f32) {
+        self.mat.iter_mut().for_each(|pheromone| *pheromone *= factor);
+    }
+
+    /// Call visit(v0, v1, value) for every edge
+    fn for_each<F: FnMut(VerticeLoc, VerticeLoc, f32)>(&self, mut visit: F) {
+        for row in 0..self.mat.nrows() {
+            for col in 0..self.mat.ncols() {
+                visit(self.vertice(row), self.vertice(col), self.mat[(row, col)]);
+            }
+        }
+    }
+
+    /// Move every edge towards the same edge of other, weight 1.0 copies other
+    fn blend(&mut self, other: &ACOGraph, weight: f32) {
+        self.mat.iter_mut()
+            .zip(other.mat.iter())
+            .for_each(|(pheromone, other)| *pheromone = (1.0 - weight) * *pheromone + weight * other);
+    }
+
     fn idx(&self, vertice: VerticeLoc) -> usize {
-        vertice.0 + vertice.1 * self.width
+        vertice.x() + vertice.y() * self.width
+    }
+
+    fn vertice(&self, idx: usize) -> VerticeLoc {
+        VerticeLoc::new(idx % self.width, idx / self.width)
+    }
+}
+
+/// Reasons for rejecting a move between two vertices
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    OutOfBounds,
+    NotAdjacent,
+    Obstacle,
+    Forbidden
+}
+
+/// How raw pheromone values are mapped onto 0..1 for rendering
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizeMode {
+    /// value / max, a single dominant edge pushes everything else towards 0
+    Linear,
+    /// Rank of the edge among all edges as a fraction, keeps contrast high
+    Percentile
+}
+
+/// Which moves an ant is allowed to make from a vertice
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Only horizontal and vertical moves
+    Four,
+    /// Horizontal, vertical and diagonal moves
+    Eight
+}
+
+/// Which paths get to deposit pheromone after every iteration
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reinforcement {
+    /// Every ant that reached the goal
+    AllAnts,
+    /// Only the cheapest path of the iteration
+    IterationBest,
+    /// Only the cheapest path found since the start of the run
+    GlobalBest
+}
+
+/// A path found by the colony together with its traversal cost. Two results are equal
+/// when they walk the same vertices, the cost follows from the path so it is left out
+/// and results can be collected into a HashSet to count distinct routes.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct PathResult {
+    pub path: Path,
+    pub cost: f32
+}
+
+impl PartialEq for PathResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Eq for PathResult {}
+
+impl std::hash::Hash for PathResult {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
+
+impl PathResult {
+    /// Edges along the path with their end points ordered, so (a, b) and (b, a) are the same edge
+    pub fn edges(&self) -> HashSet<(VerticeLoc, VerticeLoc)> {
+        self.path.windows(2).map(|edge| edge_key(edge[0], edge[1])).collect()
     }
 }
 
+/// How the cost of a single move is computed
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostModel {
+    /// Straight moves cost 1, diagonal moves sqrt(2)
+    Octile,
+    /// Every move costs 1, like a king on a chess board
+    Uniform,
+    /// A diagonal move costs as much as the two straight moves it replaces, meant for 4-connectivity
+    Manhattan
+}
+
+/// Order an ant considers the neighbours of its vertice in when picking a move
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighbourOrder {
+    /// Column by column from the top left, the same on every move
+    Fixed,
+    /// Shuffled anew on every move, drawn from the random stream of the ant
+    Shuffled,
+    /// The fixed order started at a random place on every move, cheaper than a shuffle
+    Rotating
+}
+
+/// Pheromone laid on every edge the moment an ant walks it, on top of the deposit once it
+/// reaches the goal
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OnlineDeposit {
+    pub per_step: f32
+}
+
+/// Aggregate outcome of ACOMap::simulate
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimStats {
+    /// Mean cost of the successful runs, None if no run succeeded
+    pub mean_cost: Option<f32>,
+    /// Standard deviation of the cost of the successful runs, None if no run succeeded
+    pub std_cost: Option<f32>,
+    /// Fraction of the runs that found a path
+    pub success_rate: f32,
+    /// Mean iteration, counted from 1, in which the successful runs found their final best
+    /// path, None if no run succeeded
+    pub mean_iterations: Option<f32>
+}
+
+#[derive(Clone)]
 pub struct ACOMap {
     pheromone_graph: ACOGraph,
-    _evaporation_rate: f32
+    obstacles: HashSet<VerticeLoc>,
+    forbidden_edges: HashSet<(VerticeLoc, VerticeLoc)>,
+    connectivity: Connectivity,
+    cost_model: CostModel,
+    min_likelihood: f32,
+    dirty_vertices: HashSet<VerticeLoc>,
+    dirty_edges: HashSet<(VerticeLoc, VerticeLoc)>,
+    ant_count: usize,
+    iterations: usize,
+    max_path_len: usize,
+    reinforcement: Reinforcement,
+    global_best_weight: f32,
+    restart_on_stagnation: Option<usize>,
+    threads: Option<usize>,
+    /// Built once when the thread count is set and shared by every copy of the map
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    seed: Option<u64>,
+    ant_profiles: Vec<AntProfile>,
+    anti_pheromone_weight: f32,
+    recent_usage: HashMap<(VerticeLoc, VerticeLoc), f32>,
+    record_history: bool,
+    history: VecDeque<f32>,
+    history_capacity: usize,
+    source_pheromone: HashMap<(VerticeLoc, VerticeLoc), Vec<f32>>,
+    fuel: Option<f32>,
+    online_deposit: Option<OnlineDeposit>,
+    pruned: HashSet<VerticeLoc>,
+    random_tie_break: bool,
+    search_bounds: Option<(VerticeLoc, VerticeLoc)>,
+    search_margin: Option<usize>,
+    init_jitter: f32,
+    evaporation_map: Option<Vec<f32>>,
+    deposit_amount: f32,
+    max_pheromone: f32,
+    neighbour_order: NeighbourOrder,
+    alternative_goals: Vec<VerticeLoc>,
+    goal: Option<VerticeLoc>,
+    start: Option<VerticeLoc>,
+    evaporation_rate: f32
 }
 
-impl ACOMap {
+/// Evaporation rate used when none is given to the builder
+pub const DEFAULT_EVAPORATION_RATE: f32 = 0.1;
+
+/// Builds an ACOMap after validating the dimensions and evaporation rate
+pub struct ACOMapBuilder {
+    width: usize,
+    height: usize,
+    evaporation_rate: f32,
+    init_jitter: f32,
+    seed: Option<u64>
+}
+
+impl ACOMapBuilder {
+    pub fn new(width: usize, height: usize) -> Self {
+        ACOMapBuilder {
+            width,
+            height,
+            evaporation_rate: DEFAULT_EVAPORATION_RATE,
+            init_jitter: 0.0,
+            seed: None
+        }
+    }
+
+    pub fn evaporation_rate(mut self, evaporation_rate: f32) -> Self {
+        self.evaporation_rate = evaporation_rate;
+        self
+    }
+
+    /// Spread the initial pheromone uniformly over [1 - jitter, 1 + jitter] instead of
+    /// starting every edge at exactly 1.0, helps the colony break symmetry sooner
     #[allow(dead_code)]
-    pub fn new(width: usize, height: usize, evaporation_rate: f32) -> Option<Self> {
-        if width == 0 || height == 0 || evaporation_rate > 1.0 {
-            return None;
+    pub fn init_jitter(mut self, jitter: f32) -> Self {
+        self.init_jitter = jitter;
+        self
+    }
+
+    /// Master seed of the built map, see ACOMap::set_seed. Also seeds the initial jitter.
+    #[allow(dead_code)]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> Result<ACOMap, AcoError> {
+        let (width, height, evaporation_rate) = (self.width, self.height, self.evaporation_rate);
+        if width == 0 || height == 0 {
+            return Err(AcoError::InvalidDimensions(width, height));
         }
+        validate_evaporation_rate(evaporation_rate)?;
         let mut aco_map = ACOMap {
             pheromone_graph: ACOGraph::new(width, height),
-            _evaporation_rate: evaporation_rate
+            obstacles: HashSet::new(),
+            forbidden_edges: HashSet::new(),
+            connectivity: Connectivity::Eight,
+            cost_model: CostModel::Octile,
+            min_likelihood: 0.0,
+            dirty_vertices: HashSet::new(),
+            dirty_edges: HashSet::new(),
+            ant_count: 10,
+            iterations: 50,
+            max_path_len: width * height,
+            reinforcement: Reinforcement::AllAnts,
+            global_best_weight: 0.0,
+            restart_on_stagnation: None,
+            threads: None,
+            thread_pool: None,
+            seed: self.seed,
+            ant_profiles: vec![AntProfile::default()],
+            anti_pheromone_weight: 0.0,
+            recent_usage: HashMap::new(),
+            record_history: false,
+            history: VecDeque::new(),
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            source_pheromone: HashMap::new(),
+            fuel: None,
+            online_deposit: None,
+            pruned: HashSet::new(),
+            random_tie_break: true,
+            search_bounds: None,
+            search_margin: None,
+            init_jitter: self.init_jitter.clamp(0.0, 1.0),
+            evaporation_map: None,
+            deposit_amount: 1.0,
+            max_pheromone: DEFAULT_MAX_PHEROMONE,
+            neighbour_order: NeighbourOrder::Fixed,
+            alternative_goals: Vec::new(),
+            goal: None,
+            start: None,
+            evaporation_rate
         };
-        aco_map.pheromone_graph.mat.fill(1.0);
-        return Some(aco_map);
+        aco_map.pheromone_graph.fill(INITIAL_PHEROMONE);
+        aco_map.apply_init_jitter();
+        Ok(aco_map)
     }
+}
 
-    /// Get the cost for traversing from vertice v0 to v1
+impl ACOMap {
+    /// Create a map with the default evaporation rate.
+    ///
+    /// # Panics
+    /// Panics with "ACOMap::new: width and height must be non-zero" if either dimension is 0,
+    /// use try_new or ACOMapBuilder to handle invalid input.
     #[allow(dead_code)]
-    fn cost(v0: VerticeLoc, v1: VerticeLoc) -> f32 {
-        const SQRT_OF_2: f32 = 1.41421356237;
-        if v0.0 != v1.0 && v0.1 != v1.1 {
-            SQRT_OF_2
-        } else {
-            1.0
+    pub fn new(width: usize, height: usize) -> Self {
+        if width == 0 || height == 0 {
+            panic!("ACOMap::new: width and height must be non-zero");
         }
+        ACOMapBuilder::new(width, height)
+            .build()
+            .expect("ACOMap::new: default evaporation rate is valid")
     }
 
+    pub fn try_new(width: usize, height: usize, evaporation_rate: f32) -> Result<Self, AcoError> {
+        ACOMapBuilder::new(width, height)
+            .evaporation_rate(evaporation_rate)
+            .build()
+    }
+
+    /// Create a map with the pheromone on edges taken from an external source. Every edge
+    /// has to join two neighbouring vertices inside the map, edges that are left out keep
+    /// the default pheromone. Edges are undirected, so an edge listed in both directions
+    /// must carry the same value.
     #[allow(dead_code)]
-    fn get_neighbours(&self, vertice: VerticeLoc) -> Vec<VerticeLoc> {
-        let mut neighbours: Vec<VerticeLoc> = Vec::new();
-        for i in &[-1, 0, 1] {
-            let new_x = (vertice.0 as i32) + i;
-            if new_x < 0 || new_x >= self.pheromone_graph.width as i32 {
-                // Resulting vertice will be outside map
-                continue;
+    pub fn with_pheromone_matrix(width: usize, height: usize, evaporation_rate: f32,
+        edges: &HashMap<(VerticeLoc, VerticeLoc), f32>) -> Result<Self, AcoError> {
+        let mut map = ACOMap::try_new(width, height, evaporation_rate)?;
+        for ((v0, v1), pheromone) in edges {
+            let (v0, v1) = (map.validated(*v0)?, map.validated(*v1)?);
+            let mirrored = edges.get(&(v1, v0)).unwrap_or(pheromone);
+            if !map.is_adjacent(v0, v1) || !pheromone.is_finite() || *pheromone < 0.0 || mirrored != pheromone {
+                return Err(AcoError::InvalidEdge(v0, v1));
             }
-            for j in &[-1, 0, 1] {
-                let new_y = (vertice.1 as i32) + j;
-                if new_y < 0 || new_y >= self.pheromone_graph.height as i32 || (*i == 0 && *j == 0) {
-                    // Resulting vertice will be outside map
-                    continue;
-                }
+            map.pheromone_graph.set_edg_value(v0, v1, *pheromone);
+        }
+        Ok(map)
+    }
 
-                neighbours.push((new_x as usize, new_y as usize));
+    /// Change the dimensions of the map. Pheromone between vertices that exist in both the
+    /// old and new dimensions is kept, new edges start at the default. Obstacles, forbidden
+    /// edges and a goal outside the new bounds are dropped, so is the evaporation map. A
+    /// max_path_len left at its default follows the new number of vertices.
+    #[allow(dead_code)]
+    pub fn resize(&mut self, new_width: usize, new_height: usize) -> Result<(), AcoError> {
+        if new_width == 0 || new_height == 0 {
+            return Err(AcoError::InvalidDimensions(new_width, new_height));
+        }
+        if self.max_path_len == self.pheromone_graph.width * self.pheromone_graph.height {
+            self.max_path_len = new_width * new_height;
+        }
+        let mut resized = ACOGraph::new(new_width, new_height);
+        resized.fill(INITIAL_PHEROMONE);
+        let fits = |vertice: VerticeLoc| vertice.x() < new_width && vertice.y() < new_height;
+        self.pheromone_graph.for_each(|v0, v1, value| {
+            if fits(v0) && fits(v1) {
+                resized.set_edg_value(v0, v1, value);
             }
+        });
+        self.pheromone_graph = resized;
+
+        self.obstacles.retain(|vertice| fits(*vertice));
+        self.pruned.retain(|vertice| fits(*vertice));
+        self.forbidden_edges.retain(|(v0, v1)| fits(*v0) && fits(*v1));
+        self.dirty_vertices.retain(|vertice| fits(*vertice));
+        self.dirty_edges.retain(|(v0, v1)| fits(*v0) && fits(*v1));
+        self.goal = self.goal.filter(|goal| fits(*goal));
+        self.start = self.start.filter(|start| fits(*start));
+        self.evaporation_map = None;
+        Ok(())
+    }
+
+    /// Build a map with one vertice per pixel of the image at path where every pixel
+    /// darker than threshold becomes an obstacle. Images with more than MAX_IMAGE_VERTICES
+    /// pixels are rejected.
+    #[cfg(feature = "image")]
+    #[allow(dead_code)]
+    pub fn from_image<P: AsRef<std::path::Path>>(path: P, threshold: u8) -> Result<ACOMap, AcoError> {
+        let image = image::open(path)
+            .map_err(|err| AcoError::Image(err.to_string()))?
+            .to_luma8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        if width.saturating_mul(height) > MAX_IMAGE_VERTICES {
+            return Err(AcoError::ImageTooLarge(width, height));
         }
-        return neighbours;
+        let mut map = ACOMap::try_new(width, height, DEFAULT_EVAPORATION_RATE)?;
+        image.enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel.0[0] < threshold)
+            .try_for_each(|(x, y, _)| map.set_obstacle(VerticeLoc::new(x as usize, y as usize)))?;
+        Ok(map)
     }
 
+    /// Choose between 4 and 8 connected movement, defaults to 8
     #[allow(dead_code)]
-    fn get_neighbours_with_exclusions(&self, vertice: VerticeLoc, exclusions: &Vec<VerticeLoc>) -> Vec<VerticeLoc> {
-        let mut neighbours: Vec<VerticeLoc> = Vec::new();
-        for i in &[-1, 0, 1] {
-            let new_x = (vertice.0 as i32) + i;
-            if new_x < 0 || new_x >= self.pheromone_graph.width as i32 {
-                // Resulting vertice will be outside map
-                continue;
+    pub fn set_connectivity(&mut self, connectivity: Connectivity) {
+        self.connectivity = connectivity;
+    }
+
+    #[allow(dead_code)]
+    pub fn connectivity(&self) -> Connectivity {
+        self.connectivity
+    }
+
+    /// Choose how moves are priced, used for both the likelihood and path costs. Defaults to Octile
+    #[allow(dead_code)]
+    pub fn set_cost_model(&mut self, cost_model: CostModel) {
+        self.cost_model = cost_model;
+    }
+
+    #[allow(dead_code)]
+    pub fn cost_model(&self) -> CostModel {
+        self.cost_model
+    }
+
+    /// Number of ants released in every iteration of find_path
+    #[allow(dead_code)]
+    pub fn set_ant_count(&mut self, ant_count: usize) {
+        self.ant_count = ant_count;
+    }
+
+    /// Number of iterations find_path runs before returning the best path found
+    #[allow(dead_code)]
+    pub fn set_iterations(&mut self, iterations: usize) {
+        self.iterations = iterations;
+    }
+
+    /// Change the evaporation rate between iterations, it has to be in [0, 1)
+    #[allow(dead_code)]
+    pub fn set_evaporation_rate(&mut self, evaporation_rate: f32) -> Result<(), AcoError> {
+        self.evaporation_rate = validate_evaporation_rate(evaporation_rate)?;
+        Ok(())
+    }
+
+    /// Let evaporation vary over the map with one rate per vertice, indexed by x + y * width.
+    /// An edge evaporates at the higher rate of its two end points. None goes back to the
+    /// global evaporation rate everywhere.
+    #[allow(dead_code)]
+    pub fn set_evaporation_map(&mut self, evaporation_map: Option<Vec<f32>>) -> Result<(), AcoError> {
+        if let Some(rates) = &evaporation_map {
+            if rates.len() != self.vertex_count() {
+                return Err(AcoError::InvalidEvaporationMap(rates.len()));
             }
-            for j in &[-1, 0, 1] {
-                let new_y = (vertice.1 as i32) + j;
-                if new_y < 0 || new_y >= self.pheromone_graph.height as i32 || (*i == 0 && *j == 0) {
-                    // Resulting vertice will be outside map
-                    continue;
-                }
+            for rate in rates {
+                validate_evaporation_rate(*rate)?;
+            }
+        }
+        self.evaporation_map = evaporation_map;
+        Ok(())
+    }
 
-                let neighbour: VerticeLoc = (new_x as usize, new_y as usize);
-                if !exclusions.contains(&neighbour) {
-                    neighbours.push(neighbour);
+    #[allow(dead_code)]
+    pub fn evaporation_rate(&self) -> f32 {
+        self.evaporation_rate
+    }
+
+    /// Let ants lay pheromone on every edge as they walk it, None only deposits once an ant
+    /// reached the goal. A colony then builds its ants one after the other, see
+    /// construct_paths_online.
+    #[allow(dead_code)]
+    pub fn set_online_deposit(&mut self, online_deposit: Option<OnlineDeposit>) {
+        self.online_deposit = online_deposit;
+    }
+
+    pub fn online_deposit(&self) -> Option<OnlineDeposit> {
+        self.online_deposit
+    }
+
+    /// Lay the online deposit on the edge an ant just walked, if enabled
+    pub fn deposit_online(&mut self, v0: VerticeLoc, v1: VerticeLoc) -> Result<(), AcoError> {
+        match self.online_deposit {
+            Some(OnlineDeposit {per_step}) => self.deposit_pheromone(&[v0, v1], per_step),
+            None => Ok(())
+        }
+    }
+
+    /// Total cost an ant may spend before it has to give up, None for unlimited
+    #[allow(dead_code)]
+    pub fn set_fuel(&mut self, fuel: Option<f32>) {
+        self.fuel = fuel;
+    }
+
+    /// Number of vertices after which an ant gives up, defaults to the number of vertices
+    #[allow(dead_code)]
+    pub fn set_max_path_len(&mut self, max_path_len: usize) {
+        self.max_path_len = max_path_len;
+    }
+
+    /// Choose which paths deposit pheromone after every iteration, defaults to all ants
+    #[allow(dead_code)]
+    pub fn set_reinforcement(&mut self, reinforcement: Reinforcement) {
+        self.reinforcement = reinforcement;
+    }
+
+    pub fn reinforcement(&self) -> Reinforcement {
+        self.reinforcement
+    }
+
+    /// Pheromone Q an ant lays on its path, spread as Q / cost. Together with the
+    /// evaporation rate it sets the level pheromone settles at, defaults to 1.0.
+    #[allow(dead_code)]
+    pub fn set_deposit_amount(&mut self, deposit_amount: f32) -> Result<(), AcoError> {
+        if !deposit_amount.is_finite() || deposit_amount <= 0.0 {
+            return Err(AcoError::InvalidDepositAmount(deposit_amount));
+        }
+        self.deposit_amount = deposit_amount;
+        Ok(())
+    }
+
+    pub fn deposit_amount(&self) -> f32 {
+        self.deposit_amount
+    }
+
+    /// Hard cap no deposit can push the pheromone of an edge above, keeps repeated deposits
+    /// on a dominant edge from overflowing
+    #[allow(dead_code)]
+    pub fn set_max_pheromone(&mut self, max_pheromone: f32) -> Result<(), AcoError> {
+        if !max_pheromone.is_finite() || max_pheromone <= MIN_PHEROMONE {
+            return Err(AcoError::InvalidMaxPheromone(max_pheromone));
+        }
+        self.max_pheromone = max_pheromone;
+        Ok(())
+    }
+
+    /// Extra deposit on the best path found so far on top of the regular reinforcement,
+    /// weight / cost is added every iteration. Defaults to 0.0, negative values are treated as 0.0.
+    #[allow(dead_code)]
+    pub fn set_global_best_weight(&mut self, weight: f32) {
+        self.global_best_weight = weight.max(0.0);
+    }
+
+    pub fn global_best_weight(&self) -> f32 {
+        self.global_best_weight
+    }
+
+    /// Reset the pheromone and re-seed the best path after this many iterations
+    /// without improvement, None never restarts
+    #[allow(dead_code)]
+    pub fn set_restart_on_stagnation(&mut self, iterations: Option<usize>) {
+        self.restart_on_stagnation = iterations;
+    }
+
+    pub fn restart_on_stagnation(&self) -> Option<usize> {
+        self.restart_on_stagnation
+    }
+
+    /// Number of worker threads the ants of an iteration are split across. The map builds a
+    /// rayon pool of that size once and reuses it for every iteration, None uses the global pool.
+    #[allow(dead_code)]
+    pub fn set_threads(&mut self, threads: Option<usize>) -> Result<(), AcoError> {
+        let threads = threads.filter(|threads| *threads > 0);
+        self.thread_pool = match threads {
+            Some(threads) => {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(|err| AcoError::ThreadPool(err.to_string()))?;
+                Some(Arc::new(pool))
+            },
+            None => None
+        };
+        self.threads = threads;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn threads(&self) -> Option<usize> {
+        self.threads
+    }
+
+    /// Master seed every colony derives its random stream from, None seeds from entropy.
+    /// With a seed set searches are reproducible regardless of the thread count.
+    #[allow(dead_code)]
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Let one ant walk from each of starts to goal, spread over the worker threads, see
+    /// set_threads. Results keep the order of starts, ants that fail are left out.
+    pub fn construct_paths<R: Rng + ?Sized>(&self, starts: &[VerticeLoc], goal: VerticeLoc,
+        rng: &mut R) -> Vec<PathResult> {
+        // Every ant gets its own stream drawn from rng up front, so the outcome does not
+        // depend on how the ants are spread over the threads
+        let ants: Vec<(VerticeLoc, &AntProfile, u64)> = starts.iter()
+            .enumerate()
+            .map(|(ant, start)| (*start, &self.ant_profiles[self.profile_index(ant)], rng.gen::<u64>()))
+            .collect();
+        let construct = |(start, profile, seed): &(VerticeLoc, &AntProfile, u64)| {
+            self.construct_path_with_profile(*start, goal, profile, &mut StdRng::seed_from_u64(*seed))
+        };
+        if ants.len() <= 1 {
+            return ants.iter().filter_map(construct).collect();
+        }
+        self.install(|| ants.par_iter().filter_map(construct).collect())
+    }
+
+    /// Run op on the pool set with set_threads, on rayon's global pool without one
+    fn install<T: Send, OP: FnOnce() -> T + Send>(&self, op: OP) -> T {
+        match &self.thread_pool {
+            Some(pool) => pool.install(op),
+            None => op()
+        }
+    }
+
+    /// Same as construct_paths but every ant lays the online deposit along its path before the
+    /// next one sets out, so the later ants of an iteration already follow it. Always runs on
+    /// a single thread since every ant depends on the ones before it. Ants that fail leave
+    /// no trail.
+    pub fn construct_paths_online<R: Rng + ?Sized>(&mut self, starts: &[VerticeLoc], goal: VerticeLoc,
+        rng: &mut R) -> Vec<PathResult> {
+        let mut results = Vec::new();
+        for (ant, start) in starts.iter().enumerate() {
+            let profile = self.ant_profiles[self.profile_index(ant)];
+            let mut ant_rng = StdRng::seed_from_u64(rng.gen::<u64>());
+            if let Some(result) = self.construct_path_with_profile(*start, goal, &profile, &mut ant_rng) {
+                for edge in result.path.windows(2) {
+                    self.deposit_online(edge[0], edge[1]).expect("Ants only walk vertices of the map");
                 }
+                results.push(result);
             }
         }
-        return neighbours;
+        results
     }
 
-    fn get_likelyhood_factor(&self, v0: VerticeLoc, v1: VerticeLoc) -> f32 {
-        let pheromone = self.pheromone_graph.get_edg_value(v0, v1);
-        let cost = ACOMap::cost(v0, v1);
-        pheromone / cost
+    /// Composition of the colony, ant i of every iteration uses profile i % profiles.len().
+    /// An empty list falls back to the single default profile.
+    #[allow(dead_code)]
+    pub fn set_ant_profiles(&mut self, profiles: Vec<AntProfile>) {
+        self.ant_profiles = if profiles.is_empty() { vec![AntProfile::default()] } else { profiles };
     }
 
+    pub fn ant_profiles(&self) -> &[AntProfile] {
+        &self.ant_profiles
+    }
+
+    /// Index of the profile used by ant number ant of an iteration
+    pub fn profile_index(&self, ant: usize) -> usize {
+        ant % self.ant_profiles.len()
+    }
+
+    /// How strongly recently used edges repel ants, 0.0 disables the anti-pheromone
     #[allow(dead_code)]
-    pub fn get_next_vertice(&self, current: VerticeLoc) -> Option<VerticeLoc> {
-        let mut likelyhood_sum = 0.0;
+    pub fn set_anti_pheromone_weight(&mut self, weight: f32) {
+        self.anti_pheromone_weight = weight.max(0.0);
+    }
 
-        use crate::roulette::RouletteSubjects;
-        let mut neighbours = RouletteSubjects::<VerticeLoc>(
-            self.get_neighbours(current)
-                .iter()
-                .map(|neighbour| {
-                    let likelyhood = self.get_likelyhood_factor(current, *neighbour);
-                    likelyhood_sum += likelyhood;
-                    (likelyhood, *neighbour)
-                })
-                .collect()
-        );
+    /// Order ants consider neighbours in, so first match logic does not always favour the
+    /// same direction. Defaults to Fixed. The order is drawn from the random stream of each
+    /// ant, get_neighbours and the searches built on it always use the fixed order.
+    #[allow(dead_code)]
+    pub fn set_neighbour_order(&mut self, neighbour_order: NeighbourOrder) {
+        self.neighbour_order = neighbour_order;
+    }
 
-        if neighbours.len() == 0 {
-            return None
+    /// Break ties between equally attractive neighbours at random when an ant exploits,
+    /// otherwise the last of them in neighbour order is taken
+    #[allow(dead_code)]
+    pub fn set_random_tie_break(&mut self, random_tie_break: bool) {
+        self.random_tie_break = random_tie_break;
+    }
+
+    /// Remember that an ant just walked from v0 to v1
+    pub fn record_move(&mut self, v0: VerticeLoc, v1: VerticeLoc) {
+        if self.anti_pheromone_weight > 0.0 {
+            *self.recent_usage.entry(edge_key(v0, v1)).or_insert(0.0) += 1.0;
         }
+    }
 
-        neighbours.iter_mut().for_each(|pair| {pair.0 = pair.0 / likelyhood_sum});
-        neighbours.roulette()
+    /// Let the recent edge usage fade, edges that are barely used any more are forgotten
+    pub fn decay_recent_usage(&mut self) {
+        self.recent_usage.values_mut().for_each(|usage| *usage *= RECENT_USAGE_RETAINED);
+        self.recent_usage.retain(|_, usage| *usage > 1e-3);
     }
 
+    /// Keep the best cost of every iteration of find_path around for plotting
     #[allow(dead_code)]
-    pub fn get_next_vertice_with_exclusions(&self, current: VerticeLoc, exclusions: &Vec<VerticeLoc>) -> Option<VerticeLoc> {
-        use crate::roulette::RouletteSubjects;
-        let mut likelyhood_sum = 0.0;
-        let mut neighbours = RouletteSubjects::<VerticeLoc>(
-            self.get_neighbours_with_exclusions(current, exclusions)
-                .iter()
-                .map(|neighbour| {
-                    let likelyhood = self.get_likelyhood_factor(current, *neighbour);
-                    likelyhood_sum += likelyhood;
-                    (likelyhood, *neighbour)
-                })
-                .collect() 
-        );
+    pub fn set_record_history(&mut self, record_history: bool) {
+        self.record_history = record_history;
+    }
 
-        if neighbours.len() == 0 {
-            return None;
+    /// Number of iterations the cost history holds on to, older ones are dropped so memory
+    /// stays flat however long a run gets
+    #[allow(dead_code)]
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
         }
-        
-        neighbours.iter_mut().for_each(|pair| pair.0 = pair.0 / likelyhood_sum);
-        neighbours.roulette()
     }
 
+    /// Best cost after each of the most recent iterations of the latest search since its
+    /// last goal change, oldest first, infinite until a path was found
     #[allow(dead_code)]
-    fn find_path(_v0: VerticeLoc, _v1: VerticeLoc) -> Vec<VerticeLoc> {
-        Vec::new()
+    pub fn cost_history(&self) -> Vec<f32> {
+        self.history.iter().cloned().collect()
     }
 
-    #[allow(dead_code)]
-    pub fn render(&self, window_size: (usize, usize), graphics: &mut Graphics2D) {
-        let x_spacing = window_size.0 as f32 / self.pheromone_graph.width as f32;
-        let y_spacing = (window_size.1 as f32 - x_spacing) / (self.pheromone_graph.height - 1) as f32;
-        let r = if x_spacing < y_spacing { x_spacing / 20.0 } else { y_spacing / 20.0 };
-        let x_offs = x_spacing / 2.0;
-        let y_offs = x_offs;
+    /// Forget the recorded costs, done by every new colony and on a goal change
+    pub fn clear_cost_history(&mut self) {
+        self.history.clear();
+    }
 
-        for i in 0..self.pheromone_graph.width {
-            let x = x_offs + i as f32 * x_spacing;
-            for j in 0..self.pheromone_graph.height {
-                let y = y_offs + j as f32 * y_spacing;
-                graphics.draw_circle((x, y), r, Color::GRAY);
+    /// Append the best cost of an iteration to the history if it is being recorded
+    pub fn record_best_cost(&mut self, cost: f32) {
+        if self.record_history && self.history_capacity > 0 {
+            if self.history.len() == self.history_capacity {
+                self.history.pop_front();
             }
+            self.history.push_back(cost);
         }
     }
 
+    pub fn ant_count(&self) -> usize {
+        self.ant_count
+    }
+
     #[allow(dead_code)]
-    pub fn get_vertice_coordinates(&self, window_size: (usize, usize), vertice: VerticeLoc) -> (f32, f32) {
-        let x_spacing = window_size.0 as f32 / self.pheromone_graph.width as f32;
-        let y_spacing = (window_size.1 as f32 - x_spacing) / (self.pheromone_graph.height - 1) as f32;
-        let x_offs = x_spacing / 2.0;
-        let y_offs = x_offs;
-        let x = x_offs + vertice.0 as f32 * x_spacing;
-        let y = y_offs + vertice.1 as f32 * y_spacing;
-        (x, y)
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Steer ants towards goal, the likelyhood of a neighbour is weighted by how close
+    /// it is to the goal. Can be changed between iterations to chase a moving target.
+    pub fn set_goal(&mut self, goal: Option<VerticeLoc>) -> Result<(), AcoError> {
+        self.goal = goal.map(|goal| self.validated(goal)).transpose()?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn goal(&self) -> Option<VerticeLoc> {
+        self.goal
+    }
+
+    /// Vertice the next search starts from, only used to keep it out of prune_dead_ends
+    #[allow(dead_code)]
+    pub fn set_start(&mut self, start: Option<VerticeLoc>) -> Result<(), AcoError> {
+        self.start = start.map(|start| self.validated(start)).transpose()?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn start(&self) -> Option<VerticeLoc> {
+        self.start
+    }
+
+    /// Set a floor that is added to the likelyhood of every neighbour, so that no
+    /// neighbour ever gets a zero probability of being picked even when its pheromone
+    /// has decayed away. Defaults to 0.0, negative values are treated as 0.0.
+    #[allow(dead_code)]
+    pub fn set_min_likelihood(&mut self, min_likelihood: f32) {
+        self.min_likelihood = min_likelihood.max(0.0);
+    }
+
+    /// Mark a vertice as an obstacle, ants will never step onto it
+    #[allow(dead_code)]
+    pub fn set_obstacle(&mut self, vertice: VerticeLoc) -> Result<(), AcoError> {
+        let vertice = self.validated(vertice)?;
+        self.obstacles.insert(vertice);
+        self.pruned.remove(&vertice);
+        self.dirty_vertices.insert(vertice);
+        Ok(())
+    }
+
+    /// Block every dead end, a vertice with at most one neighbour, over and over until none
+    /// are left, so ants never wander into them. The start and goal are never pruned. The
+    /// pruned vertices act as obstacles until restore_pruned is called, returns how many were pruned.
+    #[allow(dead_code)]
+    pub fn prune_dead_ends(&mut self) -> usize {
+        let keep = [self.start, self.goal];
+        let mut pruned = 0;
+        loop {
+            let dead_ends: Vec<VerticeLoc> = (0..self.pheromone_graph.height)
+                .flat_map(|y| (0..self.pheromone_graph.width).map(move |x| VerticeLoc::new(x, y)))
+                .filter(|vertice| !self.is_obstacle(*vertice) && !keep.contains(&Some(*vertice)))
+                .filter(|vertice| self.map_neighbours(*vertice).len() <= 1)
+                .collect();
+            if dead_ends.is_empty() {
+                return pruned;
+            }
+            pruned += dead_ends.len();
+            for vertice in dead_ends {
+                self.obstacles.insert(vertice);
+                self.dirty_vertices.insert(vertice);
+                self.pruned.insert(vertice);
+            }
+        }
     }
+
+    /// Turn every vertice blocked by prune_dead_ends back into a free one, unless it has
+    /// since been made a real obstacle with set_obstacle
+    #[allow(dead_code)]
+    pub fn restore_pruned(&mut self) {
+        for vertice in std::mem::take(&mut self.pruned) {
+            self.clear_obstacle(vertice).expect("Only vertices on the map are pruned");
+        }
+    }
+
+    /// Make a previously blocked vertice traversable again
+    #[allow(dead_code)]
+    pub fn clear_obstacle(&mut self, vertice: VerticeLoc) -> Result<(), AcoError> {
+        let vertice = self.validated(vertice)?;
+        self.obstacles.remove(&vertice);
+        self.dirty_vertices.insert(vertice);
+        Ok(())
+    }
+
+    /// Make every blocked vertice traversable again, pruned ones included
+    #[allow(dead_code)]
+    pub fn clear_obstacles(&mut self) {
+        self.dirty_vertices.extend(self.obstacles.drain());
+        self.pruned.clear();
+    }
+
+    /// Number of blocked vertices, the search bounds play no part
+    #[allow(dead_code)]
+    pub fn obstacle_count(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_obstacle(&self, vertice: VerticeLoc) -> bool {
+        self.obstacles.contains(&vertice)
+    }
+
+    /// Whether an ant may stand on vertice: on the map, not an obstacle and inside the
+    /// search bounds if any are set
+    pub fn is_traversable(&self, vertice: VerticeLoc) -> bool {
+        self.in_bounds(vertice) && !self.is_obstacle(vertice) && self.in_search_bounds(vertice)
+    }
+
+    /// Keep ants inside the rectangle spanned by the two corners, both included.
+    /// None lets them roam the whole map.
+    #[allow(dead_code)]
+    pub fn set_search_bounds(&mut self, bounds: Option<(VerticeLoc, VerticeLoc)>) {
+        self.search_bounds = bounds.map(|(v0, v1)| (
+            VerticeLoc::new(v0.x().min(v1.x()), v0.y().min(v1.y())),
+            VerticeLoc::new(v0.x().max(v1.x()), v0.y().max(v1.y()))
+        ));
+    }
+
+    #[allow(dead_code)]
+    pub fn search_bounds(&self) -> Option<(VerticeLoc, VerticeLoc)> {
+        self.search_bounds
+    }
+
+    /// When no search bounds are set, every search is kept to the bounding box of its starts
+    /// and goal grown by margin vertices on every side, or the whole map if the goal can not
+    /// be reached inside that box. None searches the whole map.
+    #[allow(dead_code)]
+    pub fn set_search_margin(&mut self, margin: Option<usize>) {
+        self.search_margin = margin;
+    }
+
+    fn in_search_bounds(&self, vertice: VerticeLoc) -> bool {
+        match self.search_bounds {
+            Some((min, max)) => (min.x()..=max.x()).contains(&vertice.x()) && (min.y()..=max.y()).contains(&vertice.y()),
+            None => true
+        }
+    }
+
+    /// Set the search bounds from the search margin for a search from starts to goal, see
+    /// set_search_margin. Returns true if bounds were set, the caller has to lift them again
+    /// once the search is done.
+    pub fn apply_search_margin(&mut self, starts: &[VerticeLoc], goal: VerticeLoc) -> bool {
+        let margin = match (self.search_bounds, self.search_margin) {
+            (None, Some(margin)) => margin,
+            _ => return false
+        };
+        let corners: Vec<VerticeLoc> = starts.iter().cloned().chain([goal]).collect();
+        self.search_bounds = Some(self.bounding_box(&corners, margin));
+        if !starts.iter().any(|start| self.is_reachable(*start, goal)) {
+            self.search_bounds = None;
+            return false;
+        }
+        true
+    }
+
+    /// Bounding box of vertices grown by margin, clamped to the map
+    fn bounding_box(&self, vertices: &[VerticeLoc], margin: usize) -> (VerticeLoc, VerticeLoc) {
+        let min_x = vertices.iter().map(|v| v.x()).min().unwrap_or(0).saturating_sub(margin);
+        let min_y = vertices.iter().map(|v| v.y()).min().unwrap_or(0).saturating_sub(margin);
+        let max_x = (vertices.iter().map(|v| v.x()).max().unwrap_or(0) + margin).min(self.pheromone_graph.width - 1);
+        let max_y = (vertices.iter().map(|v| v.y()).max().unwrap_or(0) + margin).min(self.pheromone_graph.height - 1);
+        (VerticeLoc::new(min_x, min_y), VerticeLoc::new(max_x, max_y))
+    }
+
+    /// Check whether goal can be reached from start by walking between neighbours
+    #[allow(dead_code)]
+    pub fn is_reachable(&self, start: VerticeLoc, goal: VerticeLoc) -> bool {
+        self.reachable_set(start).contains(&goal)
+    }
+
+    /// Flood fill from start, following the same moves an ant can make. Empty if start
+    /// is not traversable.
+    #[allow(dead_code)]
+    pub fn reachable_set(&self, start: VerticeLoc) -> HashSet<VerticeLoc> {
+        if !self.is_traversable(start) {
+            return HashSet::new();
+        }
+
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(vertice) = queue.pop_front() {
+            for neighbour in self.get_neighbours(vertice) {
+                if visited.insert(neighbour) {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        visited
+    }
+
+    /// Path from start to goal with as few moves as possible, found with a breadth first
+    /// search that ignores pheromone and move costs. None if goal can not be reached.
+    #[allow(dead_code)]
+    pub fn shortest_hop_path(&self, start: VerticeLoc, goal: VerticeLoc) -> Option<Vec<VerticeLoc>> {
+        if !self.is_traversable(start) || !self.is_traversable(goal) {
+            return None;
+        }
+
+        let mut came_from: HashMap<VerticeLoc, VerticeLoc> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(vertice) = queue.pop_front() {
+            if vertice == goal {
+                let mut path = vec![goal];
+                while let Some(previous) = came_from.get(path.last().unwrap()) {
+                    path.push(*previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbour in self.get_neighbours(vertice) {
+                if neighbour != start && !came_from.contains_key(&neighbour) {
+                    came_from.insert(neighbour, vertice);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        None
+    }
+
+    /// Turn roughly a density fraction of the free vertices into obstacles while keeping
+    /// start and goal free and connected. Layouts that disconnect them are redrawn a number
+    /// of times, after which a straight corridor between them is carved out instead, a
+    /// staircase under 4-connectivity. Fails with Unreachable if forbidden edges still keep
+    /// them apart.
+    #[allow(dead_code)]
+    pub fn random_obstacles<R: Rng + ?Sized>(&mut self, density: f32, start: VerticeLoc, goal: VerticeLoc,
+        rng: &mut R) -> Result<(), AcoError> {
+        const MAX_ATTEMPTS: usize = 100;
+
+        if !(0.0..1.0).contains(&density) {
+            return Err(AcoError::InvalidDensity(density));
+        }
+        self.validated(start)?;
+        self.validated(goal)?;
+
+        let fixed_obstacles = self.obstacles.clone();
+        for _ in 0..MAX_ATTEMPTS {
+            self.obstacles = fixed_obstacles.clone();
+            for y in 0..self.pheromone_graph.height {
+                for x in 0..self.pheromone_graph.width {
+                    let vertice = VerticeLoc::new(x, y);
+                    if vertice != start && vertice != goal && rng.gen::<f32>() < density {
+                        self.obstacles.insert(vertice);
+                    }
+                }
+            }
+            self.obstacles.remove(&start);
+            self.obstacles.remove(&goal);
+            if self.is_reachable(start, goal) {
+                break;
+            }
+        }
+
+        if !self.is_reachable(start, goal) {
+            let line = self.line_cells(start, goal);
+            for (i, vertice) in line.iter().enumerate() {
+                self.obstacles.remove(vertice);
+                if self.connectivity == Connectivity::Four && i > 0 {
+                    // Step around the corner of a diagonal move
+                    self.obstacles.remove(&VerticeLoc::new(vertice.x(), line[i - 1].y()));
+                }
+            }
+        }
+
+        let mut changed: Vec<VerticeLoc> = self.obstacles.symmetric_difference(&fixed_obstacles).cloned().collect();
+        changed.push(start);
+        changed.push(goal);
+        self.dirty_vertices.extend(changed);
+        if !self.is_reachable(start, goal) {
+            return Err(AcoError::Unreachable(goal));
+        }
+        Ok(())
+    }
+
+    /// Disallow moving directly between v0 and v1 in either direction
+    #[allow(dead_code)]
+    pub fn forbid_edge(&mut self, v0: VerticeLoc, v1: VerticeLoc) -> Result<(), AcoError> {
+        let (v0, v1) = (self.validated(v0)?, self.validated(v1)?);
+        self.forbidden_edges.insert(edge_key(v0, v1));
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn allow_edge(&mut self, v0: VerticeLoc, v1: VerticeLoc) -> Result<(), AcoError> {
+        let (v0, v1) = (self.validated(v0)?, self.validated(v1)?);
+        self.forbidden_edges.remove(&edge_key(v0, v1));
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn is_forbidden(&self, v0: VerticeLoc, v1: VerticeLoc) -> bool {
+        self.forbidden_edges.contains(&edge_key(v0, v1))
+    }
+
+    pub fn in_bounds(&self, vertice: VerticeLoc) -> bool {
+        vertice.x() < self.pheromone_graph.width && vertice.y() < self.pheromone_graph.height
+    }
+
+    /// Single bounds check shared by the public entry points so out of range input
+    /// always fails with OutOfBounds
+    fn validated(&self, vertice: VerticeLoc) -> Result<VerticeLoc, AcoError> {
+        if self.in_bounds(vertice) { Ok(vertice) } else { Err(AcoError::OutOfBounds) }
+    }
+
+    /// The offset between v0 and v1 is a single move under the current connectivity
+    fn is_connected_offset(&self, v0: VerticeLoc, v1: VerticeLoc) -> bool {
+        let (dx, dy) = v0.delta(v1);
+        if v0 == v1 || dx.abs() > 1 || dy.abs() > 1 {
+            return false;
+        }
+        self.connectivity == Connectivity::Eight || dx == 0 || dy == 0
+    }
+
+    /// Both vertices are in bounds, one move apart under the current connectivity and the
+    /// edge between them is not forbidden. Obstacles are not considered.
+    #[allow(dead_code)]
+    pub fn is_adjacent(&self, v0: VerticeLoc, v1: VerticeLoc) -> bool {
+        self.in_bounds(v0) && self.in_bounds(v1) && self.is_connected_offset(v0, v1) && !self.is_forbidden(v0, v1)
+    }
+
+    /// Check that an ant standing at from is allowed to step onto to
+    #[allow(dead_code)]
+    pub fn try_move(&self, from: VerticeLoc, to: VerticeLoc) -> Result<(), MoveError> {
+        if !self.in_bounds(from) || !self.in_bounds(to) {
+            return Err(MoveError::OutOfBounds);
+        }
+        if !self.is_connected_offset(from, to) {
+            return Err(MoveError::NotAdjacent);
+        }
+        if !self.is_traversable(to) {
+            return Err(MoveError::Obstacle);
+        }
+        if self.is_forbidden(from, to) {
+            return Err(MoveError::Forbidden);
+        }
+        Ok(())
+    }
+
+    /// Get the cost for traversing from vertice v0 to v1
+    #[allow(dead_code)]
+    fn cost(&self, v0: VerticeLoc, v1: VerticeLoc) -> f32 {
+        if v0.x() != v1.x() && v0.y() != v1.y() {
+            self.diagonal_cost()
+        } else {
+            self.straight_cost()
+        }
+    }
+
+    /// Cost of a horizontal or vertical move
+    #[allow(dead_code)]
+    pub fn straight_cost(&self) -> f32 {
+        STRAIGHT_COST
+    }
+
+    /// Cost of a diagonal move
+    #[allow(dead_code)]
+    pub fn diagonal_cost(&self) -> f32 {
+        match self.cost_model {
+            CostModel::Octile => DIAGONAL_COST,
+            CostModel::Uniform => STRAIGHT_COST,
+            CostModel::Manhattan => 2.0 * STRAIGHT_COST
+        }
+    }
+
+    /// Lower bound of the cost between v0 and v1 on an obstacle free map: as many diagonal
+    /// moves as possible and straight moves for the rest
+    pub fn octile_distance(&self, v0: VerticeLoc, v1: VerticeLoc) -> f32 {
+        let (dx, dy) = v0.delta(v1);
+        let (dx, dy) = (dx.unsigned_abs() as f32, dy.unsigned_abs() as f32);
+        let diagonal_steps = dx.min(dy);
+        let straight_steps = dx.max(dy) - diagonal_steps;
+        diagonal_steps * self.diagonal_cost() + straight_steps * self.straight_cost()
+    }
+
+    #[allow(dead_code)]
+    fn get_neighbours(&self, vertice: VerticeLoc) -> Vec<VerticeLoc> {
+        let mut neighbours: Vec<VerticeLoc> = Vec::new();
+        self.fill_neighbours(vertice, &mut neighbours);
+        return neighbours;
+    }
+
+    /// Same as get_neighbours but clears and refills neighbours instead of allocating,
+    /// so a single buffer can be reused for every lookup in a hot loop
+    pub fn fill_neighbours(&self, vertice: VerticeLoc, neighbours: &mut Vec<VerticeLoc>) {
+        self.collect_neighbours(vertice, neighbours, true);
+    }
+
+    /// Neighbours of vertice on the whole map, the search bounds are ignored. For changes to
+    /// the map itself, which must not depend on the search that happens to be running.
+    fn map_neighbours(&self, vertice: VerticeLoc) -> Vec<VerticeLoc> {
+        let mut neighbours = Vec::new();
+        self.collect_neighbours(vertice, &mut neighbours, false);
+        neighbours
+    }
+
+    fn collect_neighbours(&self, vertice: VerticeLoc, neighbours: &mut Vec<VerticeLoc>, within_search_bounds: bool) {
+        neighbours.clear();
+        if !self.in_bounds(vertice) {
+            // Edges of a vertice off the map would alias onto the row below it
+            return;
+        }
+        for i in &[-1, 0, 1] {
+            let new_x = (vertice.x() as i32) + i;
+            if new_x < 0 || new_x >= self.pheromone_graph.width as i32 {
+                // Resulting vertice will be outside map
+                continue;
+            }
+            for j in &[-1, 0, 1] {
+                let new_y = (vertice.y() as i32) + j;
+                if new_y < 0 || new_y >= self.pheromone_graph.height as i32 || (*i == 0 && *j == 0) {
+                    // Resulting vertice will be outside map
+                    continue;
+                }
+                if self.connectivity == Connectivity::Four && *i != 0 && *j != 0 {
+                    // Diagonal moves are not allowed
+                    continue;
+                }
+
+                let neighbour = VerticeLoc::new(new_x as usize, new_y as usize);
+                if self.is_obstacle(neighbour) || self.is_forbidden(vertice, neighbour) {
+                    continue;
+                }
+                if !within_search_bounds || self.in_search_bounds(neighbour) {
+                    neighbours.push(neighbour);
+                }
+            }
+        }
+    }
+
+    /// Same as get_neighbours but listed in the configured neighbour order, drawn from rng
+    #[allow(dead_code)]
+    pub fn get_neighbours_ordered<R: Rng + ?Sized>(&self, vertice: VerticeLoc, rng: &mut R) -> Vec<VerticeLoc> {
+        let mut neighbours = self.get_neighbours(vertice);
+        self.order_neighbours(&mut neighbours, rng);
+        neighbours
+    }
+
+    /// Rearrange neighbours from the fixed order into the configured neighbour order
+    fn order_neighbours<R: Rng + ?Sized>(&self, neighbours: &mut [VerticeLoc], rng: &mut R) {
+        match self.neighbour_order {
+            NeighbourOrder::Fixed => (),
+            NeighbourOrder::Shuffled => {
+                use rand::seq::SliceRandom;
+                neighbours.shuffle(rng);
+            },
+            NeighbourOrder::Rotating => {
+                if !neighbours.is_empty() {
+                    neighbours.rotate_left(rng.gen_range(0..neighbours.len()));
+                }
+            }
+        }
+    }
+
+    /// Get every edge leaving vertice as (neighbour, pheromone) pairs
+    #[allow(dead_code)]
+    pub fn edges_of(&self, vertice: VerticeLoc) -> Vec<(VerticeLoc, f32)> {
+        self.get_neighbours(vertice)
+            .into_iter()
+            .map(|neighbour| (neighbour, self.pheromone_graph.get_edg_value(vertice, neighbour)))
+            .collect()
+    }
+
+    /// Get the neighbours of vertice ordered by descending pheromone, ties are broken by
+    /// ascending coordinate so the order is deterministic
+    #[allow(dead_code)]
+    pub fn neighbours_sorted_by_pheromone(&self, vertice: VerticeLoc) -> Vec<(VerticeLoc, f32)> {
+        let mut edges = self.edges_of(vertice);
+        edges.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        edges
+    }
+
+    /// Number of vertices an ant standing at vertice can step onto
+    #[allow(dead_code)]
+    pub fn neighbour_count(&self, vertice: VerticeLoc) -> usize {
+        self.get_neighbours(vertice).len()
+    }
+
+    /// neighbour_count of every vertice at once, indexed by x + y * width. Vertices that can
+    /// not be stood on get 0.
+    #[allow(dead_code)]
+    pub fn neighbour_count_map(&self) -> Vec<u8> {
+        let mut neighbours = Vec::with_capacity(8);
+        (0..self.vertex_count())
+            .map(|idx| {
+                let vertice = self.pheromone_graph.vertice(idx);
+                if !self.is_traversable(vertice) {
+                    return 0;
+                }
+                self.fill_neighbours(vertice, &mut neighbours);
+                neighbours.len() as u8
+            })
+            .collect()
+    }
+
+    /// Total pheromone on the edges from vertice to every neighbour an ant could step onto
+    #[allow(dead_code)]
+    pub fn neighbour_pheromone_sum(&self, vertice: VerticeLoc) -> f32 {
+        self.get_neighbours(vertice)
+            .into_iter()
+            .map(|neighbour| self.pheromone_graph.get_edg_value(vertice, neighbour))
+            .sum()
+    }
+
+    /// Every vertice next to some vertice of path without being on path itself,
+    /// the corridor a local search can move the path around in
+    #[allow(dead_code)]
+    pub fn neighbours_of_path(&self, path: &[VerticeLoc]) -> HashSet<VerticeLoc> {
+        path.iter()
+            .filter(|vertice| self.in_bounds(**vertice))
+            .flat_map(|vertice| self.get_neighbours(*vertice))
+            .filter(|neighbour| !path.contains(neighbour))
+            .collect()
+    }
+
+    /// Every neighbour of vertice together with its (dx, dy) offset from vertice
+    #[allow(dead_code)]
+    pub fn neighbour_directions(&self, vertice: VerticeLoc) -> Vec<((i32, i32), VerticeLoc)> {
+        self.get_neighbours(vertice)
+            .into_iter()
+            .map(|neighbour| (vertice.delta(neighbour), neighbour))
+            .collect()
+    }
+
+    /// Neighbours of vertice that lie roughly in direction dir, i.e. whose offset
+    /// from vertice has a positive dot product with dir
+    #[allow(dead_code)]
+    pub fn neighbours_toward(&self, vertice: VerticeLoc, dir: (i32, i32)) -> Vec<VerticeLoc> {
+        self.get_neighbours(vertice)
+            .into_iter()
+            .filter(|neighbour| {
+                let (dx, dy) = vertice.delta(*neighbour);
+                dx * dir.0 + dy * dir.1 > 0
+            })
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    fn get_neighbours_with_exclusions(&self, vertice: VerticeLoc, exclusions: &Vec<VerticeLoc>) -> Vec<VerticeLoc> {
+        self.get_neighbours_where(vertice, |neighbour| !exclusions.contains(&neighbour))
+    }
+
+    /// Neighbours of vertice for which predicate holds, for rule based exclusions
+    pub fn get_neighbours_where<P: Fn(VerticeLoc) -> bool>(&self, vertice: VerticeLoc, predicate: P) -> Vec<VerticeLoc> {
+        self.get_neighbours(vertice)
+            .into_iter()
+            .filter(|neighbour| predicate(*neighbour))
+            .collect()
+    }
+
+    fn get_likelyhood_factor(&self, v0: VerticeLoc, v1: VerticeLoc) -> f32 {
+        let pheromone = self.pheromone_graph.get_edg_value(v0, v1);
+        let cost = self.cost(v0, v1);
+        self.repelled(v0, v1, pheromone / cost * self.goal_heuristic(v1) + self.min_likelihood)
+    }
+
+    /// Subtract the weighted recent usage of the edge from likelyhood, never going below MIN_PHEROMONE
+    fn repelled(&self, v0: VerticeLoc, v1: VerticeLoc, likelyhood: f32) -> f32 {
+        if self.anti_pheromone_weight <= 0.0 {
+            return likelyhood;
+        }
+        match self.recent_usage.get(&edge_key(v0, v1)) {
+            Some(usage) => (likelyhood - self.anti_pheromone_weight * usage).max(MIN_PHEROMONE),
+            None => likelyhood
+        }
+    }
+
+    /// Same as get_likelyhood_factor with pheromone raised to alpha and the heuristic to beta
+    fn get_likelyhood_factor_for(&self, profile: &AntProfile, v0: VerticeLoc, v1: VerticeLoc) -> f32 {
+        if *profile == AntProfile::default() {
+            return self.get_likelyhood_factor(v0, v1);
+        }
+        let pheromone = self.pheromone_graph.get_edg_value(v0, v1);
+        let heuristic = self.goal_heuristic(v1) / self.cost(v0, v1);
+        self.repelled(v0, v1, pheromone.powf(profile.alpha) * heuristic.powf(profile.beta) + self.min_likelihood)
+    }
+
+    /// Attractiveness of vertice with regards to the current goal, 1.0 when there is no goal
+    fn goal_heuristic(&self, vertice: VerticeLoc) -> f32 {
+        match self.goal {
+            Some(goal) => {
+                let distance = |goal: &VerticeLoc| {
+                    let (dx, dy) = vertice.delta(*goal);
+                    ((dx * dx + dy * dy) as f32).sqrt()
+                };
+                let nearest = self.alternative_goals.iter().map(distance).fold(distance(&goal), f32::min);
+                1.0 / (1.0 + nearest)
+            },
+            None => 1.0
+        }
+    }
+
+    /// Pick the next vertice for an ant standing at current, Ok(None) if it has nowhere to go
+    #[allow(dead_code)]
+    pub fn get_next_vertice(&self, current: VerticeLoc) -> Result<Option<VerticeLoc>, AcoError> {
+        let current = self.validated(current)?;
+
+        use crate::roulette::RouletteSubjects;
+        let mut neighbours = RouletteSubjects::<VerticeLoc>(
+            self.get_neighbours(current)
+                .iter()
+                .map(|neighbour| (self.get_likelyhood_factor(current, *neighbour), *neighbour))
+                .collect()
+        );
+
+        if neighbours.len() == 0 {
+            return Ok(None)
+        }
+
+        neighbours.normalize();
+        Ok(neighbours.roulette())
+    }
+
+    /// Same as get_next_vertice but never picks one of exclusions
+    #[allow(dead_code)]
+    pub fn get_next_vertice_with_exclusions(&self, current: VerticeLoc,
+        exclusions: &Vec<VerticeLoc>) -> Result<Option<VerticeLoc>, AcoError> {
+        let current = self.validated(current)?;
+        use crate::roulette::RouletteSubjects;
+        let mut neighbours = RouletteSubjects::<VerticeLoc>(
+            self.get_neighbours_with_exclusions(current, exclusions)
+                .iter()
+                .map(|neighbour| (self.get_likelyhood_factor(current, *neighbour), *neighbour))
+                .collect()
+        );
+
+        if neighbours.len() == 0 {
+            return Ok(None);
+        }
+
+        neighbours.normalize();
+        Ok(neighbours.roulette())
+    }
+
+    /// Deterministic counterpart of get_next_vertice_with_exclusions: the neighbour not in
+    /// exclusions with the highest likelyhood, ties go to the lowest coordinate
+    #[allow(dead_code)]
+    pub fn neighbour_best_move(&self, current: VerticeLoc, exclusions: &[VerticeLoc]) -> Option<VerticeLoc> {
+        self.get_neighbours_where(current, |neighbour| !exclusions.contains(&neighbour))
+            .into_iter()
+            .map(|neighbour| (self.get_likelyhood_factor(current, neighbour), neighbour))
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal).then_with(|| b.1.cmp(&a.1)))
+            .map(|(_, neighbour)| neighbour)
+    }
+
+    /// Same as get_next_vertice_with_exclusions for an ant following profile. With probability
+    /// q0 the most attractive neighbour is taken, otherwise one is sampled.
+    fn get_next_vertice_for<R: Rng + ?Sized>(&self, profile: &AntProfile, current: VerticeLoc,
+        exclusions: &Vec<VerticeLoc>, rng: &mut R) -> Option<VerticeLoc> {
+        use crate::roulette::RouletteSubjects;
+        let mut candidates = self.get_neighbours_with_exclusions(current, exclusions);
+        self.order_neighbours(&mut candidates, rng);
+        let mut neighbours = RouletteSubjects::<VerticeLoc>(
+            candidates
+                .into_iter()
+                .map(|neighbour| (self.get_likelyhood_factor_for(profile, current, neighbour), neighbour))
+                .collect()
+        );
+        neighbours.normalize();
+
+        if profile.q0 > 0.0 && rng.gen::<f32>() < profile.q0 {
+            if self.random_tie_break {
+                return neighbours.best(rng);
+            }
+            return neighbours.0.iter()
+                .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+                .map(|pair| pair.1);
+        }
+        neighbours.sample(rng)
+    }
+
+    /// Pheromone on each edge between consecutive vertices of path, to see where a trail is
+    /// strong or weak. Fails with Unreachable for the first vertice that is not adjacent to
+    /// the one before it.
+    #[allow(dead_code)]
+    pub fn path_pheromones(&self, path: &[VerticeLoc]) -> Result<Vec<f32>, AcoError> {
+        path.iter().try_for_each(|vertice| self.validated(*vertice).map(|_| ()))?;
+        path.windows(2)
+            .map(|edge| {
+                if self.is_adjacent(edge[0], edge[1]) {
+                    Ok(self.pheromone_graph.get_edg_value(edge[0], edge[1]))
+                } else {
+                    Err(AcoError::Unreachable(edge[1]))
+                }
+            })
+            .collect()
+    }
+
+    /// Sum of the pheromone on every traversable edge, each undirected edge counted once
+    #[allow(dead_code)]
+    pub fn total_pheromone(&self) -> f32 {
+        self.edges().into_iter().map(|(v0, v1)| self.pheromone_graph.get_edg_value(v0, v1)).sum()
+    }
+
+    /// Advisory guess at how hard a search from start to goal is, useful to pick an
+    /// iteration budget. The detour of the shortest hop path compared to the straight
+    /// line is scaled up by the share of obstacles on that line. An open map gives 1.0,
+    /// higher values suggest more iterations and an unreachable goal gives infinity.
+    #[allow(dead_code)]
+    pub fn difficulty_estimate(&self, start: VerticeLoc, goal: VerticeLoc) -> f32 {
+        let hops = match self.shortest_hop_path(start, goal) {
+            Some(path) => path.len() - 1,
+            None => return f32::INFINITY
+        };
+        let (dx, dy) = start.delta(goal);
+        let straight_hops = match self.connectivity {
+            Connectivity::Eight => dx.unsigned_abs().max(dy.unsigned_abs()),
+            Connectivity::Four => dx.unsigned_abs() + dy.unsigned_abs()
+        };
+        if straight_hops == 0 {
+            return 1.0;
+        }
+
+        let line = self.line_cells(start, goal);
+        let blocked = line.iter().filter(|vertice| self.is_obstacle(**vertice)).count();
+        let obstacle_density = blocked as f32 / line.len() as f32;
+        hops as f32 / straight_hops as f32 * (1.0 + obstacle_density)
+    }
+
+    /// Check that every traversable edge carries a finite pheromone value, reports the first
+    /// edge that does not. Cheap enough to run after every iteration in debug builds.
+    #[allow(dead_code)]
+    pub fn validate_field(&self) -> Result<(), AcoError> {
+        match self.edges().into_iter().find(|(v0, v1)| !self.pheromone_graph.get_edg_value(*v0, *v1).is_finite()) {
+            Some((v0, v1)) => Err(AcoError::NonFinitePheromone(v0, v1)),
+            None => Ok(())
+        }
+    }
+
+    /// Shannon entropy, in nats, of the probabilities an ant standing at vertice picks each
+    /// neighbour with. Close to 0.0 once the colony has settled on a single edge, close to
+    /// ln of the neighbour count while it is still undecided. 0.0 without neighbours.
+    #[allow(dead_code)]
+    pub fn transition_entropy(&self, vertice: VerticeLoc) -> f32 {
+        let likelyhoods: Vec<f32> = self.get_neighbours(vertice)
+            .into_iter()
+            .map(|neighbour| self.get_likelyhood_factor(vertice, neighbour))
+            .collect();
+        let sum: f32 = likelyhoods.iter().sum();
+        if sum <= 0.0 || !sum.is_finite() {
+            return 0.0;
+        }
+        likelyhoods.iter()
+            .map(|likelyhood| likelyhood / sum)
+            .filter(|probability| *probability > 0.0)
+            .map(|probability| -probability * probability.ln())
+            .sum()
+    }
+
+    /// Number of vertices in the map, obstacles included
+    #[allow(dead_code)]
+    pub fn vertex_count(&self) -> usize {
+        self.pheromone_graph.width * self.pheromone_graph.height
+    }
+
+    /// Number of undirected edges between traversable neighbours under the current connectivity
+    #[allow(dead_code)]
+    pub fn edge_count(&self) -> usize {
+        self.edges().len()
+    }
+
+    /// Get every undirected edge between traversable neighbours exactly once, as (low, high) pairs
+    #[allow(dead_code)]
+    fn edges(&self) -> Vec<(VerticeLoc, VerticeLoc)> {
+        let mut edges = Vec::new();
+        self.for_each_edge(|v0, v1| edges.push((v0, v1)));
+        edges
+    }
+
+    /// Call visit(v0, v1) once for every edge without collecting them, see edges
+    fn for_each_edge<F: FnMut(VerticeLoc, VerticeLoc)>(&self, mut visit: F) {
+        for y in 0..self.pheromone_graph.height {
+            for x in 0..self.pheromone_graph.width {
+                let vertice = VerticeLoc::new(x, y);
+                if self.is_obstacle(vertice) {
+                    continue;
+                }
+                self.map_neighbours(vertice)
+                    .into_iter()
+                    .filter(|neighbour| *neighbour > vertice)
+                    .for_each(|neighbour| visit(vertice, neighbour));
+            }
+        }
+    }
+
+    /// Get the total cost of traversing path, infinite if it jumps between vertices that
+    /// are not adjacent
+    #[allow(dead_code)]
+    pub fn path_cost(&self, path: &[VerticeLoc]) -> f32 {
+        path.windows(2)
+            .map(|edge| if self.is_adjacent(edge[0], edge[1]) { self.cost(edge[0], edge[1]) } else { f32::INFINITY })
+            .sum()
+    }
+
+    /// Let every edge lose a fraction evaporation_rate of its pheromone, or the local rate
+    /// when an evaporation map is set, never dropping below MIN_PHEROMONE. Every edge is
+    /// marked as changed.
+    #[allow(dead_code)]
+    pub fn evaporate(&mut self) {
+        let edges = self.edges();
+        let (width, evaporation_rate) = (self.pheromone_graph.width, self.evaporation_rate);
+        let rates = self.evaporation_map.as_deref();
+        let retained = |v0: VerticeLoc, v1: VerticeLoc| match rates {
+            Some(rates) => 1.0 - rates[v0.x() + v0.y() * width].max(rates[v1.x() + v1.y() * width]),
+            None => 1.0 - evaporation_rate
+        };
+
+        for (v0, v1) in &edges {
+            let pheromone = self.pheromone_graph.get_edg_value(*v0, *v1) * retained(*v0, *v1);
+            self.pheromone_graph.set_edg_value(*v0, *v1, pheromone.max(MIN_PHEROMONE));
+        }
+        for ((v0, v1), contributions) in self.source_pheromone.iter_mut() {
+            let retained = retained(*v0, *v1);
+            contributions.iter_mut().for_each(|contribution| *contribution *= retained);
+        }
+        self.dirty_edges.extend(edges);
+    }
+
+    /// Amount of initial pheromone jitter, see ACOMapBuilder::init_jitter. Takes effect
+    /// on the next reset_pheromones, 0.0 keeps the field uniform.
+    #[allow(dead_code)]
+    pub fn set_init_jitter(&mut self, jitter: f32) {
+        self.init_jitter = jitter.clamp(0.0, 1.0);
+    }
+
+    /// Add the initial jitter on top of a freshly filled field, drawn from the map seed
+    fn apply_init_jitter(&mut self) {
+        if self.init_jitter <= 0.0 {
+            return;
+        }
+        let mut rng = stream_rng(self.seed, JITTER_STREAM);
+        for (v0, v1) in self.edges() {
+            let pheromone = self.initial_pheromone(&mut rng);
+            self.pheromone_graph.set_edg_value(v0, v1, pheromone);
+        }
+    }
+
+    /// Initial pheromone level of a single edge, spread by init_jitter
+    fn initial_pheromone<R: Rng + ?Sized>(&self, rng: &mut R) -> f32 {
+        if self.init_jitter <= 0.0 {
+            return INITIAL_PHEROMONE;
+        }
+        (INITIAL_PHEROMONE + rng.gen_range(-self.init_jitter..=self.init_jitter)).max(MIN_PHEROMONE)
+    }
+
+    /// Add uniform noise in [-magnitude, magnitude] to every edge, clamped to
+    /// [MIN_PHEROMONE, max_pheromone]. Shakes a stagnating colony loose while keeping the
+    /// trails it has built, unlike reset_pheromones.
+    #[allow(dead_code)]
+    pub fn perturb_pheromone<R: Rng + ?Sized>(&mut self, magnitude: f32, rng: &mut R) {
+        if magnitude.is_nan() || magnitude <= 0.0 {
+            return;
+        }
+        for (v0, v1) in self.edges() {
+            let pheromone = self.pheromone_graph.get_edg_value(v0, v1) + rng.gen_range(-magnitude..=magnitude);
+            self.pheromone_graph.set_edg_value(v0, v1, pheromone.clamp(MIN_PHEROMONE, self.max_pheromone));
+            self.dirty_edges.insert(edge_key(v0, v1));
+        }
+    }
+
+    /// Blend the pheromone of other into this map as (1 - weight) * self + weight * other,
+    /// weight is clamped to [0, 1]. Both maps need the same dimensions and connectivity.
+    #[allow(dead_code)]
+    pub fn merge_pheromone(&mut self, other: &ACOMap, weight: f32) -> Result<(), AcoError> {
+        let same_shape = self.pheromone_graph.width == other.pheromone_graph.width
+            && self.pheromone_graph.height == other.pheromone_graph.height;
+        if !same_shape || self.connectivity != other.connectivity {
+            return Err(AcoError::MapMismatch);
+        }
+        let weight = weight.clamp(0.0, 1.0);
+        self.pheromone_graph.blend(&other.pheromone_graph, weight);
+        let edges = self.edges();
+        self.dirty_edges.extend(edges);
+        Ok(())
+    }
+
+    /// Put the edges around vertice back to the initial pheromone level, jittered like
+    /// reset_pheromones does, for when the map changed there and the old trails no longer make sense
+    pub fn reset_edges_of(&mut self, vertice: VerticeLoc) -> Result<(), AcoError> {
+        let vertice = self.validated(vertice)?;
+        let mut rng = stream_rng(self.seed, JITTER_STREAM);
+        for i in -1..=1 {
+            for j in -1..=1 {
+                let (x, y) = (vertice.x() as i64 + i, vertice.y() as i64 + j);
+                if x < 0 || y < 0 || (i == 0 && j == 0) {
+                    continue;
+                }
+                let neighbour = VerticeLoc::new(x as usize, y as usize);
+                if !self.in_bounds(neighbour) {
+                    continue;
+                }
+                let pheromone = self.initial_pheromone(&mut rng);
+                self.pheromone_graph.set_edg_value(vertice, neighbour, pheromone);
+                self.dirty_edges.insert(edge_key(vertice, neighbour));
+            }
+        }
+        Ok(())
+    }
+
+    /// Put every edge back to the initial pheromone level, jittered if init_jitter is set
+    pub fn reset_pheromones(&mut self) {
+        self.pheromone_graph.fill(INITIAL_PHEROMONE);
+        self.apply_init_jitter();
+        let edges = self.edges();
+        self.dirty_edges.extend(edges);
+    }
+
+    /// Lay a trail along a known path so the ants are steered toward it after a reset
+    pub fn seed_from_path(&mut self, result: &PathResult) -> Result<(), AcoError> {
+        self.deposit_pheromone_inverse_cost(result)
+    }
+
+    /// Reinforce path with the deposit amount Q divided by its cost
+    #[allow(dead_code)]
+    pub fn deposit_pheromone_inverse_cost(&mut self, result: &PathResult) -> Result<(), AcoError> {
+        if result.cost > 0.0 {
+            self.deposit_pheromone(&result.path, self.deposit_amount / result.cost)?;
+        }
+        Ok(())
+    }
+
+    /// Book amount of pheromone along path as laid by ants from source, only kept for
+    /// multi-source searches to tell which source dominates which edge
+    pub fn deposit_source(&mut self, source: usize, path: &[VerticeLoc], amount: f32) -> Result<(), AcoError> {
+        path.iter().try_for_each(|vertice| self.validated(*vertice).map(|_| ()))?;
+        for edge in path.windows(2) {
+            let contributions = self.source_pheromone.entry(edge_key(edge[0], edge[1])).or_default();
+            if contributions.len() <= source {
+                contributions.resize(source + 1, 0.0);
+            }
+            contributions[source] += amount;
+        }
+        Ok(())
+    }
+
+    /// Forget the per-source pheromone bookkeeping
+    pub fn clear_source_pheromone(&mut self) {
+        self.source_pheromone.clear();
+    }
+
+    /// Index of the source whose ants laid the most pheromone on the edge between v0 and v1,
+    /// None if no source deposited there
+    #[allow(dead_code)]
+    pub fn dominant_source(&self, v0: VerticeLoc, v1: VerticeLoc) -> Option<usize> {
+        self.source_pheromone.get(&edge_key(v0, v1))?
+            .iter()
+            .enumerate()
+            .filter(|(_, contribution)| **contribution > 0.0)
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(Ordering::Equal))
+            .map(|(source, _)| source)
+    }
+
+    /// Add amount of pheromone to every edge along path, up to the pheromone cap. Nothing is
+    /// deposited if the path leaves the map or jumps between vertices that are not adjacent.
+    #[allow(dead_code)]
+    pub fn deposit_pheromone(&mut self, path: &[VerticeLoc], amount: f32) -> Result<(), AcoError> {
+        path.iter().try_for_each(|vertice| self.validated(*vertice).map(|_| ()))?;
+        if let Some(edge) = path.windows(2).find(|edge| !self.is_adjacent(edge[0], edge[1])) {
+            return Err(AcoError::InvalidEdge(edge[0], edge[1]));
+        }
+        path.windows(2).for_each(|edge| {
+            let pheromone = self.pheromone_graph.get_edg_value(edge[0], edge[1]);
+            self.pheromone_graph.set_edg_value(edge[0], edge[1], (pheromone + amount).min(self.max_pheromone));
+            self.dirty_edges.insert(edge_key(edge[0], edge[1]));
+        });
+        Ok(())
+    }
+
+    /// Edges modified since the last call to render_incremental, sorted
+    #[allow(dead_code)]
+    pub fn changed_edges(&self) -> Vec<(VerticeLoc, VerticeLoc)> {
+        let mut edges: Vec<(VerticeLoc, VerticeLoc)> = self.dirty_edges.iter().cloned().collect();
+        edges.sort();
+        edges
+    }
+
+    /// Vertices modified since the last call to render_incremental, sorted
+    #[allow(dead_code)]
+    pub fn changed_vertices(&self) -> Vec<VerticeLoc> {
+        let mut vertices: Vec<VerticeLoc> = self.dirty_vertices.iter().cloned().collect();
+        vertices.sort();
+        vertices
+    }
+
+    /// Get the pheromone of every edge mapped onto 0..1 according to mode
+    #[allow(dead_code)]
+    pub fn normalized_pheromones(&self, mode: NormalizeMode) -> Vec<((VerticeLoc, VerticeLoc), f32)> {
+        let edges: Vec<((VerticeLoc, VerticeLoc), f32)> = self.edges()
+            .into_iter()
+            .map(|(v0, v1)| ((v0, v1), self.pheromone_graph.get_edg_value(v0, v1)))
+            .collect();
+        let mut sorted: Vec<f32> = edges.iter().map(|(_, pheromone)| *pheromone).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        edges.into_iter()
+            .map(|(edge, pheromone)| (edge, normalize(pheromone, &sorted, mode)))
+            .collect()
+    }
+
+    /// Approximate how central every edge is from the pheromone it accumulated, as its share
+    /// of the total pheromone. Edges the ants could not avoid come first.
+    #[allow(dead_code)]
+    pub fn edge_betweenness(&self) -> Vec<((VerticeLoc, VerticeLoc), f32)> {
+        let mut edges: Vec<((VerticeLoc, VerticeLoc), f32)> = self.edges()
+            .into_iter()
+            .map(|(v0, v1)| ((v0, v1), self.pheromone_graph.get_edg_value(v0, v1)))
+            .collect();
+        let total: f32 = edges.iter().map(|(_, pheromone)| *pheromone).sum();
+        if total > 0.0 {
+            edges.iter_mut().for_each(|(_, pheromone)| *pheromone /= total);
+        }
+        edges.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        edges
+    }
+
+    /// Get the pheromone on the edge between v0 and v1 mapped onto 0..1 according to mode.
+    /// Walks every edge once, use normalized_pheromones to normalize the whole field.
+    #[allow(dead_code)]
+    pub fn normalized_pheromone(&self, v0: VerticeLoc, v1: VerticeLoc, mode: NormalizeMode) -> f32 {
+        let value = self.pheromone_graph.get_edg_value(v0, v1);
+        let (mut max, mut below, mut equal, mut count) = (0.0_f32, 0, 0, 0);
+        self.for_each_edge(|v0, v1| {
+            let other = self.pheromone_graph.get_edg_value(v0, v1);
+            max = max.max(other);
+            below += (other < value) as usize;
+            equal += (other == value) as usize;
+            count += 1;
+        });
+        match mode {
+            NormalizeMode::Linear => normalize_linear(value, max),
+            NormalizeMode::Percentile => normalize_percentile(below, equal, count)
+        }
+    }
+
+    /// Export the graph in Graphviz DOT format with vertices pinned to their grid position.
+    /// Only edges carrying more than min_pheromone are emitted to keep big graphs readable.
+    #[allow(dead_code)]
+    pub fn to_dot(&self, min_pheromone: f32) -> String {
+        let edges: Vec<((VerticeLoc, VerticeLoc), f32)> = self.edges()
+            .into_iter()
+            .map(|(v0, v1)| ((v0, v1), self.pheromone_graph.get_edg_value(v0, v1)))
+            .filter(|(_, pheromone)| *pheromone > min_pheromone)
+            .collect();
+        let max = edges.iter().map(|(_, pheromone)| *pheromone).fold(0.0, f32::max);
+
+        let mut dot = String::from("graph aco {\n");
+        for y in 0..self.pheromone_graph.height {
+            for x in 0..self.pheromone_graph.width {
+                if self.is_obstacle(VerticeLoc::new(x, y)) {
+                    continue;
+                }
+                dot += &format!("    \"{}_{}\" [pos=\"{},{}!\"];\n", x, y, x, -(y as i64));
+            }
+        }
+        for ((v0, v1), pheromone) in edges {
+            let penwidth = if max > 0.0 { 1.0 + 4.0 * pheromone / max } else { 1.0 };
+            dot += &format!(
+                "    \"{}_{}\" -- \"{}_{}\" [weight={}, penwidth={:.2}];\n",
+                v0.x(), v0.y(), v1.x(), v1.y(), pheromone, penwidth
+            );
+        }
+        dot += "}\n";
+        dot
+    }
+
+    /// Get the pheromone weighted average position of the vertices in grid coordinates,
+    /// every edge weighs in at its midpoint. The center of the grid if there is no pheromone.
+    #[allow(dead_code)]
+    pub fn pheromone_centroid(&self) -> (f32, f32) {
+        let mut total = 0.0;
+        let mut x_sum = 0.0;
+        let mut y_sum = 0.0;
+        for (v0, v1) in self.edges() {
+            let pheromone = self.pheromone_graph.get_edg_value(v0, v1);
+            total += pheromone;
+            x_sum += pheromone * (v0.x() + v1.x()) as f32 / 2.0;
+            y_sum += pheromone * (v0.y() + v1.y()) as f32 / 2.0;
+        }
+
+        if total <= 0.0 {
+            return (
+                (self.pheromone_graph.width - 1) as f32 / 2.0,
+                (self.pheromone_graph.height - 1) as f32 / 2.0
+            );
+        }
+        (x_sum / total, y_sum / total)
+    }
+
+    /// Write the pheromone of every traversable edge as CSV, a header followed by one
+    /// x0,y0,x1,y1,pheromone row per undirected edge
+    #[allow(dead_code)]
+    pub fn export_csv<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        writeln!(w, "x0,y0,x1,y1,pheromone")?;
+        for (v0, v1) in self.edges() {
+            writeln!(w, "{},{},{},{},{}", v0.x(), v0.y(), v1.x(), v1.y(), self.pheromone_graph.get_edg_value(v0, v1))?;
+        }
+        Ok(())
+    }
+
+    /// Export the pheromone intensity around every vertice as a binary PGM image, one pixel
+    /// per vertice holding the summed pheromone of its edges scaled so the maximum is 255
+    #[allow(dead_code)]
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let (width, height) = (self.pheromone_graph.width, self.pheromone_graph.height);
+        let intensities: Vec<f32> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| VerticeLoc::new(x, y)))
+            .map(|vertice| self.edges_of(vertice).iter().map(|(_, pheromone)| pheromone).sum())
+            .collect();
+        let max = intensities.iter().cloned().fold(0.0, f32::max);
+
+        let mut pgm = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+        pgm.extend(intensities.iter().map(|intensity| {
+            if max > 0.0 { (intensity / max * 255.0).round() as u8 } else { 0 }
+        }));
+        pgm
+    }
+
+    /// Count the edges whose pheromone, normalized against the strongest edge, exceeds threshold.
+    /// A small count means the colony has converged onto a narrow corridor.
+    #[allow(dead_code)]
+    pub fn trail_concentration(&self, threshold: f32) -> usize {
+        let pheromones: Vec<f32> = self.edges()
+            .iter()
+            .map(|(v0, v1)| self.pheromone_graph.get_edg_value(*v0, *v1))
+            .collect();
+        let max = pheromones.iter().cloned().fold(0.0, f32::max);
+        if max <= 0.0 {
+            return 0;
+        }
+        pheromones.iter().filter(|pheromone| *pheromone / max > threshold).count()
+    }
+
+    /// Get the grid cells on the straight line between v0 and v1 (Bresenham),
+    /// consecutive cells are always neighbours
+    fn line_cells(&self, v0: VerticeLoc, v1: VerticeLoc) -> Vec<VerticeLoc> {
+        let (mut x, mut y) = (v0.x() as i64, v0.y() as i64);
+        let (x1, y1) = (v1.x() as i64, v1.y() as i64);
+        let dx = (x1 - x).abs();
+        let dy = -(y1 - y).abs();
+        let sx = if x < x1 { 1 } else { -1 };
+        let sy = if y < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        let mut cells = Vec::new();
+        loop {
+            cells.push(VerticeLoc::new(x as usize, y as usize));
+            if x == x1 && y == y1 {
+                return cells;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Check that none of the grid cells on the straight line between v0 and v1, both
+    /// included, is an obstacle. Where the line steps diagonally it only grazes the corners
+    /// of the two cells beside it, that is fine for one of them but squeezing between two
+    /// blocked cells is not. Vertices outside the map are never visible.
+    #[allow(dead_code)]
+    pub fn line_of_sight(&self, v0: VerticeLoc, v1: VerticeLoc) -> bool {
+        if !self.in_bounds(v0) || !self.in_bounds(v1) {
+            return false;
+        }
+        let cells = self.line_cells(v0, v1);
+        if cells.iter().any(|cell| self.is_obstacle(*cell)) {
+            return false;
+        }
+        cells.windows(2).all(|step| {
+            let (from, to) = (step[0], step[1]);
+            from.x() == to.x() || from.y() == to.y()
+                || !(self.is_obstacle(VerticeLoc::new(to.x(), from.y())) && self.is_obstacle(VerticeLoc::new(from.x(), to.y())))
+        })
+    }
+
+    /// Remove intermediate vertices of a path that can be bypassed by a straight
+    /// segment without crossing an obstacle. The result is meant for rendering,
+    /// consecutive vertices are in line of sight but not necessarily neighbours.
+    #[allow(dead_code)]
+    pub fn simplify_path(&self, path: &[VerticeLoc]) -> Vec<VerticeLoc> {
+        if path.len() < 3 {
+            return path.to_vec();
+        }
+
+        let mut simplified = vec![path[0]];
+        let mut anchor = 0;
+        while anchor < path.len() - 1 {
+            // Jump to the furthest vertice still visible from the anchor
+            let next = (anchor + 2..path.len())
+                .rev()
+                .find(|candidate| self.line_of_sight(path[anchor], path[*candidate]))
+                .unwrap_or(anchor + 1);
+            simplified.push(path[next]);
+            anchor = next;
+        }
+        simplified
+    }
+
+    /// Shorten a path by replacing detours with the straight run of cells between two of its
+    /// vertices that see each other, repeated until no replacement lowers the cost any more.
+    /// Unlike simplify_path the result is still walkable, consecutive vertices are neighbours.
+    #[allow(dead_code)]
+    pub fn improve_path(&self, path: &[VerticeLoc]) -> Vec<VerticeLoc> {
+        let mut improved = path.to_vec();
+        let mut changed = true;
+        while changed {
+            // A shortcut further down the path can open up a new one before it, so sweep again
+            changed = false;
+            let mut anchor = 0;
+            while anchor + 2 < improved.len() {
+                let shortcut = (anchor + 2..improved.len())
+                    .rev()
+                    .filter(|end| self.line_of_sight(improved[anchor], improved[*end]))
+                    .map(|end| (end, self.line_cells(improved[anchor], improved[end])))
+                    .find(|(end, cells)| {
+                        cells.windows(2).all(|edge| self.try_move(edge[0], edge[1]).is_ok())
+                            && self.path_cost(cells) < self.path_cost(&improved[anchor..=*end])
+                    });
+                match shortcut {
+                    Some((end, cells)) => {
+                        improved.splice(anchor..=end, cells);
+                        changed = true;
+                    },
+                    None => anchor += 1
+                }
+            }
+        }
+        improved
+    }
+
+    /// Move the ant one vertice forward, backtracking out of dead ends on the way.
+    /// If more than max_backtracks backtracks are needed the ant is reset to its
+    /// start and Stuck is returned, so a stuck ant can never keep the caller spinning.
+    pub fn step_ant(&self, ant: &mut AntState, max_backtracks: usize) -> Result<StepOutcome, AcoError> {
+        self.validated(ant.start)?;
+        ant.path.iter().try_for_each(|vertice| self.validated(*vertice).map(|_| ()))?;
+        let mut backtracks = 0;
+        loop {
+            if let Some(next_vertice) = self.get_next_vertice_with_exclusions(ant.current, &ant.visited())? {
+                ant.advance(next_vertice);
+                return Ok(StepOutcome::Moved(next_vertice));
+            }
+
+            backtracks += 1;
+            if backtracks > max_backtracks {
+                ant.reset();
+                return Ok(StepOutcome::Stuck);
+            }
+            if !ant.backtrack() {
+                ant.reset();
+                return Ok(StepOutcome::Reset);
+            }
+        }
+    }
+
+    /// Advance every ant that is still underway by a single vertice, without backtracking.
+    /// Ants reaching their goal deposit pheromone on their route. Returns true as long as
+    /// at least one ant is still moving.
+    #[allow(dead_code)]
+    pub fn step_all_ants(&mut self, ants: &mut [AntState]) -> bool {
+        let mut active = false;
+        for ant in ants.iter_mut().filter(|ant| !ant.done) {
+            match self.get_next_vertice_with_exclusions(ant.current, &ant.path) {
+                Ok(Some(next_vertice)) => {
+                    self.record_move(ant.current, next_vertice);
+                    self.deposit_online(ant.current, next_vertice).expect("Ants only walk vertices of the map");
+                    ant.advance(next_vertice);
+                },
+                Ok(None) => ant.done = true,
+                Err(_) => {
+                    // An ant placed outside the map can neither move nor deposit
+                    ant.done = true;
+                    continue;
+                }
+            }
+
+            if ant.goal == Some(ant.current) {
+                ant.done = true;
+                let path = ant.route();
+                let cost = self.path_cost(&path);
+                // Ant routes are only ever extended by moves onto the map, a bad one was set up by hand
+                if self.deposit_pheromone_inverse_cost(&PathResult {path: path.into(), cost}).is_err() {
+                    continue;
+                }
+            } else if ant.path.len() >= self.max_path_len {
+                ant.done = true;
+            }
+            active |= !ant.done;
+        }
+        self.decay_recent_usage();
+        active
+    }
+
+    /// Let a single ant walk from start without revisiting vertices until it reaches
+    /// goal, None if it runs into a dead end, exceeds the maximum path length or runs out of fuel first.
+    /// The cost is summed up move by move so it never has to be recomputed.
+    #[allow(dead_code)]
+    pub fn construct_path(&self, start: VerticeLoc, goal: VerticeLoc) -> Option<PathResult> {
+        self.construct_path_with_profile(start, goal, &AntProfile::default(), &mut rand::thread_rng())
+    }
+
+    /// Same as construct_path for an ant following profile and drawing its moves from rng
+    pub fn construct_path_with_profile<R: Rng + ?Sized>(&self, start: VerticeLoc, goal: VerticeLoc,
+        profile: &AntProfile, rng: &mut R) -> Option<PathResult> {
+        let mut path = vec![start];
+        let mut current = start;
+        let mut cost = 0.0;
+        while current != goal && !self.alternative_goals.contains(&current) {
+            if path.len() >= self.max_path_len {
+                return None;
+            }
+            let next_vertice = self.get_next_vertice_for(profile, current, &path, rng)?;
+            cost += self.cost(current, next_vertice);
+            if self.fuel.is_some_and(|fuel| cost > fuel) {
+                return None;
+            }
+            current = next_vertice;
+            path.push(current);
+        }
+        Some(PathResult {path: path.into(), cost})
+    }
+
+    /// Search for a path from start to goal, None if no ant ever reached the goal
+    #[allow(dead_code)]
+    pub fn find_path(&mut self, start: VerticeLoc, goal: VerticeLoc) -> Option<PathResult> {
+        self.try_find_path(start, goal).ok()
+    }
+
+    /// Same as find_path but reports why no path was found. A start that is boxed in
+    /// is detected up front, before any ant is released.
+    #[allow(dead_code)]
+    pub fn try_find_path(&mut self, start: VerticeLoc, goal: VerticeLoc) -> Result<PathResult, AcoError> {
+        self.validated(start)?;
+        self.validated(goal)?;
+        if !self.is_traversable(start) || self.neighbour_count(start) == 0 {
+            return Err(AcoError::StartIsolated);
+        }
+        self.find_path_from(&[(start, 1.0)], goal).ok_or(AcoError::NoPathFound)
+    }
+
+    /// Same as find_path but reports the progress of the search to observer
+    #[allow(dead_code)]
+    pub fn find_path_observed(&mut self, start: VerticeLoc, goal: VerticeLoc,
+        observer: &mut dyn SolverObserver) -> Option<PathResult> {
+        self.find_path_from_observed(&[(start, 1.0)], goal, observer)
+    }
+
+    /// Search for a path from start to goal runs times, every run on a copy of the map with
+    /// the pheromone reset, and summarize the outcome. The map itself is left untouched.
+    #[allow(dead_code)]
+    pub fn simulate(&self, start: VerticeLoc, goal: VerticeLoc, runs: usize, iterations: usize) -> SimStats {
+        let mut costs = Vec::new();
+        let mut best_iterations = Vec::new();
+        for run in 0..runs {
+            let mut map = self.clone();
+            map.reset_pheromones();
+            let mut colony = AntColony::new(&mut map, start, goal);
+            colony.set_rng(stream_rng(self.seed, run as u64));
+            let mut best_iteration = 0;
+            for iteration in 1..=iterations {
+                if colony.step() {
+                    best_iteration = iteration;
+                }
+            }
+            if let Some(best) = colony.best() {
+                costs.push(best.cost);
+                best_iterations.push(best_iteration as f32);
+            }
+        }
+
+        let mean = |values: &[f32]| if values.is_empty() {
+            None
+        } else {
+            Some(values.iter().sum::<f32>() / values.len() as f32)
+        };
+        let mean_cost = mean(&costs);
+        let std_cost = mean_cost.and_then(|mean_cost| {
+            mean(&costs.iter().map(|cost| (cost - mean_cost).powi(2)).collect::<Vec<f32>>())
+        });
+        SimStats {
+            mean_cost,
+            std_cost: std_cost.map(f32::sqrt),
+            success_rate: if runs == 0 { 0.0 } else { costs.len() as f32 / runs as f32 },
+            mean_iterations: mean(&best_iterations)
+        }
+    }
+
+    /// Search for a path until budget runs out or the colony converges, whichever comes first,
+    /// handing every improvement of the best path to callback as it is found. At least one
+    /// iteration is run however small the budget is.
+    #[allow(dead_code)]
+    pub fn solve_with_timeout_and_callback<F: FnMut(&PathResult)>(&mut self, start: VerticeLoc, goal: VerticeLoc,
+        budget: std::time::Duration, callback: F) -> Option<PathResult> {
+        let started = std::time::Instant::now();
+        self.solve_until(start, goal, callback, |_| started.elapsed() >= budget)
+    }
+
+    /// Same as solve_with_timeout_and_callback but the budget is max_iterations instead of time
+    #[allow(dead_code)]
+    pub fn solve_with_iterations_and_callback<F: FnMut(&PathResult)>(&mut self, start: VerticeLoc, goal: VerticeLoc,
+        max_iterations: usize, callback: F) -> Option<PathResult> {
+        self.solve_until(start, goal, callback, |iterations| iterations >= max_iterations)
+    }
+
+    /// Step a colony until it converges or exhausted says so for the number of iterations run
+    fn solve_until<F: FnMut(&PathResult), E: FnMut(usize) -> bool>(&mut self, start: VerticeLoc, goal: VerticeLoc,
+        callback: F, mut exhausted: E) -> Option<PathResult> {
+        if [start, goal].iter().any(|vertice| !self.is_traversable(*vertice)) {
+            return None;
+        }
+        let mut observer = NewBestCallback(callback);
+        let mut colony = AntColony::new(self, start, goal);
+        let mut iterations = 0;
+        loop {
+            colony.step_observed(&mut observer);
+            iterations += 1;
+            if colony.converged() || exhausted(iterations) {
+                return colony.best().cloned();
+            }
+        }
+    }
+
+    /// Run the search again from a recording instead of releasing ants, the map has to be
+    /// configured as it was when the recording was made. Returns the same best path, None if
+    /// a recorded path leaves the map.
+    #[allow(dead_code)]
+    pub fn replay(&mut self, start: VerticeLoc, goal: VerticeLoc, recording: &RunRecorder) -> Option<PathResult> {
+        self.replay_observed(start, goal, recording, &mut NoopObserver)
+    }
+
+    /// Same as replay but reports the progress to observer
+    pub fn replay_observed(&mut self, start: VerticeLoc, goal: VerticeLoc, recording: &RunRecorder,
+        observer: &mut dyn SolverObserver) -> Option<PathResult> {
+        let mut colony = AntColony::new(self, start, goal);
+        for (iteration, results) in recording.iterations.iter().enumerate() {
+            colony.deposit_online(results).ok()?;
+            observer.on_paths_constructed(iteration, results);
+            colony.step_with_paths(results.clone(), observer);
+        }
+        colony.best().cloned()
+    }
+
+    /// Same as find_path but yields every improvement of the best path as soon as it is found,
+    /// each one cheaper than the one before. Yields nothing for an invalid start or goal.
+    #[allow(dead_code)]
+    pub fn find_path_anytime(&mut self, start: VerticeLoc, goal: VerticeLoc) -> impl Iterator<Item = PathResult> + '_ {
+        let valid = [start, goal].iter().all(|vertice| self.is_traversable(*vertice));
+        let iterations = if valid { self.iterations } else { 0 };
+        AnytimeSolutions::new(AntColony::new(self, start, goal), iterations)
+    }
+
+    /// Search for a path from start to whichever of goals is cheapest to reach, ants stop at
+    /// the first goal they walk onto and are steered towards the nearest one
+    #[allow(dead_code)]
+    pub fn find_path_any_goal(&mut self, start: VerticeLoc, goals: &[VerticeLoc]) -> Option<PathResult> {
+        let goals: Vec<VerticeLoc> = goals.iter()
+            .filter(|goal| self.is_traversable(**goal))
+            .cloned()
+            .collect();
+        let (goal, alternatives) = goals.split_first()?;
+        self.alternative_goals = alternatives.to_vec();
+        let best = self.find_path_from(&[(start, 1.0)], *goal);
+        self.alternative_goals.clear();
+        best
+    }
+
+    /// Search for a path to goal where every ant is released from one of starts,
+    /// picked with a probability proportional to its weight. None if any of them lies
+    /// outside the map.
+    #[allow(dead_code)]
+    pub fn find_path_from(&mut self, starts: &[(VerticeLoc, f32)], goal: VerticeLoc) -> Option<PathResult> {
+        self.find_path_from_observed(starts, goal, &mut NoopObserver)
+    }
+
+    fn find_path_from_observed(&mut self, starts: &[(VerticeLoc, f32)], goal: VerticeLoc,
+        observer: &mut dyn SolverObserver) -> Option<PathResult> {
+        let goal = self.validated(goal).ok()?;
+        starts.iter().try_for_each(|(start, _)| self.validated(*start).map(|_| ())).ok()?;
+        if !self.is_traversable(goal) {
+            return None;
+        }
+        let starts: Vec<(VerticeLoc, f32)> = starts.iter()
+            .filter(|(start, weight)| *weight > 0.0 && self.is_traversable(*start))
+            .cloned()
+            .collect();
+        if starts.is_empty() {
+            return None;
+        }
+
+        let iterations = self.iterations;
+        AntColony::with_starts(self, &starts, goal).run_observed(iterations, observer).cloned()
+    }
+
+    /// Search with several independent colonies, each on its own copy of the map. The islands
+    /// step in parallel on the worker threads, see set_threads. Every migration_interval
+    /// iterations each island is seeded with the best path of the one before it. The map takes
+    /// over the pheromone of the island holding the best path.
+    #[allow(dead_code)]
+    pub fn find_path_islands(&mut self, start: VerticeLoc, goal: VerticeLoc, colonies: usize,
+        migration_interval: usize) -> Option<PathResult> {
+        self.find_path_islands_observed(start, goal, colonies, migration_interval, &mut NoopObserver)
+    }
+
+    /// Same as find_path_islands but reports the progress to observer. The islands count as one
+    /// search, the observer only sees the best path across all of them.
+    pub fn find_path_islands_observed(&mut self, start: VerticeLoc, goal: VerticeLoc, colonies: usize,
+        migration_interval: usize, observer: &mut dyn SolverObserver) -> Option<PathResult> {
+        self.validated(start).ok()?;
+        self.validated(goal).ok()?;
+        if colonies == 0 || !self.is_traversable(start) || !self.is_traversable(goal) {
+            return None;
+        }
+
+        let mut islands: Vec<ACOMap> = (0..colonies).map(|_| self.clone()).collect();
+        let seed = self.seed;
+        let mut island_colonies: Vec<AntColony> = islands.iter_mut()
+            .enumerate()
+            .map(|(i, island)| {
+                let mut colony = AntColony::new(island, start, goal);
+                colony.set_rng(stream_rng(seed, i as u64));
+                colony
+            })
+            .collect();
+        let mut best: Option<PathResult> = None;
+        for iteration in 0..self.iterations {
+            self.install(|| island_colonies.par_iter_mut().for_each(|colony| { colony.step(); }));
+            let island_best = island_colonies.iter()
+                .filter_map(|colony| colony.best())
+                .min_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap_or(Ordering::Equal));
+            if let Some(island_best) = island_best {
+                if best.as_ref().filter(|best| best.cost <= island_best.cost).is_none() {
+                    observer.on_new_best(iteration, island_best);
+                    best = Some(island_best.clone());
+                }
+            }
+            observer.on_iteration_complete(iteration, best.as_ref());
+            if migration_interval == 0 || (iteration + 1) % migration_interval != 0 {
+                continue;
+            }
+            let bests: Vec<Option<PathResult>> = island_colonies.iter().map(|colony| colony.best().cloned()).collect();
+            for (i, colony) in island_colonies.iter_mut().enumerate() {
+                if let Some(migrant) = &bests[(i + bests.len() - 1) % bests.len()] {
+                    colony.seed(migrant).expect("Islands are copies of the same map");
+                }
+            }
+            observer.on_migration(iteration);
+        }
+
+        let (winner, best) = island_colonies.iter()
+            .enumerate()
+            .filter_map(|(i, colony)| colony.best().map(|best| (i, best.clone())))
+            .min_by(|a, b| a.1.cost.partial_cmp(&b.1.cost).unwrap_or(Ordering::Equal))?;
+        drop(island_colonies);
+        *self = islands.swap_remove(winner);
+        Some(best)
+    }
+
+    /// Find the cheapest path from start to goal with A*, ignoring pheromone altogether.
+    /// Serves as the optimal reference to measure the colony against.
+    #[allow(dead_code)]
+    pub fn astar(&self, start: VerticeLoc, goal: VerticeLoc) -> Option<PathResult> {
+        let (start, goal) = (self.validated(start).ok()?, self.validated(goal).ok()?);
+        if !self.is_traversable(start) || !self.is_traversable(goal) {
+            return None;
+        }
+
+        let mut came_from: HashMap<VerticeLoc, VerticeLoc> = HashMap::new();
+        let mut costs: HashMap<VerticeLoc, f32> = HashMap::from([(start, 0.0)]);
+        let mut open = BinaryHeap::from([OpenVertice {estimate: self.octile_distance(start, goal), vertice: start}]);
+
+        while let Some(OpenVertice {vertice, ..}) = open.pop() {
+            if vertice == goal {
+                let mut path = vec![goal];
+                while let Some(previous) = came_from.get(path.last().unwrap()) {
+                    path.push(*previous);
+                }
+                path.reverse();
+                return Some(PathResult {path: path.into(), cost: costs[&goal]});
+            }
+
+            let cost = costs[&vertice];
+            for neighbour in self.get_neighbours(vertice) {
+                let neighbour_cost = cost + self.cost(vertice, neighbour);
+                let improved = match costs.get(&neighbour) {
+                    Some(known) => neighbour_cost < *known,
+                    None => true
+                };
+                if improved {
+                    costs.insert(neighbour, neighbour_cost);
+                    came_from.insert(neighbour, vertice);
+                    open.push(OpenVertice {estimate: neighbour_cost + self.octile_distance(neighbour, goal), vertice: neighbour});
+                }
+            }
+        }
+        None
+    }
+
+    /// Run find_path and compare its cost against the optimal A* cost. The ratio is at least
+    /// 1.0, where 1.0 means the colony found an optimal path. None if either search fails.
+    #[allow(dead_code)]
+    pub fn quality_ratio(&mut self, start: VerticeLoc, goal: VerticeLoc) -> Option<f32> {
+        let optimal = self.astar(start, goal)?;
+        let found = self.find_path(start, goal)?;
+        if optimal.cost <= 0.0 {
+            return Some(1.0);
+        }
+        // Equally long paths can sum up differently in the last bit
+        Some((found.cost / optimal.cost).max(1.0))
+    }
+
+    /// Search for a path from start to goal passing through every waypoint in order. Every leg
+    /// is solved separately on the same pheromone field and the legs are joined together.
+    #[allow(dead_code)]
+    pub fn find_path_waypoints(&mut self, start: VerticeLoc, waypoints: &[VerticeLoc],
+        goal: VerticeLoc) -> Result<PathResult, AcoError> {
+        let stops: Vec<VerticeLoc> = [&[start], waypoints, &[goal]].concat();
+        stops.iter().try_for_each(|stop| self.validated(*stop).map(|_| ()))?;
+        if let Some(leg) = stops.windows(2).find(|leg| !self.is_reachable(leg[0], leg[1])) {
+            return Err(AcoError::Unreachable(leg[1]));
+        }
+
+        let mut result = PathResult {path: vec![start].into(), cost: 0.0};
+        for leg in stops.windows(2) {
+            if leg[0] == leg[1] {
+                continue;
+            }
+            let leg_result = self.try_find_path(leg[0], leg[1])?;
+            result.path.extend(leg_result.path[1..].iter().cloned());
+            result.cost += leg_result.cost;
+        }
+        Ok(result)
+    }
+
+    /// Search for a path towards a goal that moves, goal_fn gives the goal for every iteration.
+    /// Pheromone laid towards earlier goals is left to evaporate. The returned path leads to
+    /// the latest goal any ant reached, which is the goal of the last iteration unless that
+    /// one could not be reached. None as soon as goal_fn gives a goal off the map.
+    #[allow(dead_code)]
+    pub fn find_path_moving_goal(&mut self, start: VerticeLoc, mut goal_fn: impl FnMut(usize) -> VerticeLoc,
+        iterations: usize) -> Option<PathResult> {
+        let goal = goal_fn(0);
+        if !self.is_traversable(start) || !self.in_bounds(goal) {
+            return None;
+        }
+
+        let mut colony = AntColony::new(self, start, goal);
+        let mut latest = None;
+        for iteration in 0..iterations {
+            if iteration > 0 {
+                colony.set_goal(goal_fn(iteration)).ok()?;
+            }
+            colony.step();
+            if let Some(best) = colony.best() {
+                latest = Some(best.clone());
+            }
+        }
+        latest
+    }
+
+    #[allow(dead_code)]
+    pub fn render(&self, window_size: (usize, usize), graphics: &mut Graphics2D) {
+        self.render_viewport(window_size, Viewport::from_window(window_size), graphics);
+    }
+
+    /// Draw only the vertices that are visible in viewport
+    pub fn render_viewport(&self, window_size: (usize, usize), viewport: Viewport, graphics: &mut Graphics2D) {
+        if !is_drawable(window_size) {
+            return;
+        }
+        let r = self.vertice_radius(window_size);
+        for vertice in self.visible_vertices(window_size, viewport) {
+            graphics.draw_circle(self.vertice_coordinates(window_size, vertice), r, self.vertice_color(vertice));
+        }
+    }
+
+    /// Vertices whose dot overlaps viewport, row by row from the top left. Only the
+    /// visible index range is walked so the cost scales with what is on screen.
+    pub fn visible_vertices(&self, window_size: (usize, usize), viewport: Viewport) -> Vec<VerticeLoc> {
+        if !is_drawable(window_size) {
+            return Vec::new();
+        }
+        let x_spacing = window_size.0 as f32 / self.pheromone_graph.width as f32;
+        let y_spacing = (window_size.1 as f32 - x_spacing) / (self.pheromone_graph.height - 1) as f32;
+        let offs = x_spacing / 2.0;
+        let margin = self.vertice_radius(window_size);
+
+        let index_range = |min: f32, max: f32, spacing: f32, count: usize| {
+            let first = ((min - margin - offs) / spacing).ceil().max(0.0);
+            let last = ((max + margin - offs) / spacing).floor();
+            if last < first || !first.is_finite() || !last.is_finite() {
+                return 0..0;
+            }
+            first as usize..(last as usize + 1).min(count)
+        };
+        let columns = index_range(viewport.min.0, viewport.max.0, x_spacing, self.pheromone_graph.width);
+        let rows = index_range(viewport.min.1, viewport.max.1, y_spacing, self.pheromone_graph.height);
+
+        rows.flat_map(|y| columns.clone().map(move |x| VerticeLoc::new(x, y))).collect()
+    }
+
+    /// Draw the segments of path using the width and color of style
+    pub fn render_path(&self, window_size: (usize, usize), graphics: &mut Graphics2D, path: &[VerticeLoc],
+        style: &RenderStyle) {
+        if !is_drawable(window_size) {
+            return;
+        }
+        let width = style.path_width(self.cell_spacing(window_size));
+        path.windows(2).for_each(|points| {
+            graphics.draw_line(
+                self.vertice_coordinates(window_size, points[0]),
+                self.vertice_coordinates(window_size, points[1]),
+                width,
+                style.path_color
+            );
+        });
+    }
+
+    /// Draw faint lines between orthogonally adjacent vertices if the style asks for it,
+    /// meant to be drawn before anything else
+    pub fn render_grid(&self, window_size: (usize, usize), graphics: &mut Graphics2D, style: &RenderStyle) {
+        if !style.draw_grid {
+            return;
+        }
+        self.grid_lines(window_size)
+            .into_iter()
+            .for_each(|(p0, p1)| graphics.draw_line(p0, p1, 1.0, style.grid_color));
+    }
+
+    /// Endpoints of the lines between every pair of orthogonally adjacent vertices
+    pub fn grid_lines(&self, window_size: (usize, usize)) -> Vec<((f32, f32), (f32, f32))> {
+        let mut lines = Vec::new();
+        if !is_drawable(window_size) {
+            return lines;
+        }
+        for y in 0..self.pheromone_graph.height {
+            for x in 0..self.pheromone_graph.width {
+                let vertice = VerticeLoc::new(x, y);
+                let point = self.vertice_coordinates(window_size, vertice);
+                [(1, 0), (0, 1)].iter()
+                    .filter_map(|(dx, dy)| vertice.offset(*dx, *dy))
+                    .filter(|neighbour| self.in_bounds(*neighbour))
+                    .for_each(|neighbour| lines.push((point, self.vertice_coordinates(window_size, neighbour))));
+            }
+        }
+        lines
+    }
+
+    /// Write the coordinates of every vertice next to its dot with font if the style asks for
+    /// it and the grid is small enough for the labels to stay readable
+    #[allow(dead_code)]
+    pub fn render_labels(&self, window_size: (usize, usize), graphics: &mut Graphics2D, style: &RenderStyle,
+        font: &Font) {
+        if !style.show_labels {
+            return;
+        }
+        let scale = (self.cell_spacing(window_size) / 4.0).max(8.0);
+        for (_, label, position) in self.vertice_labels(window_size) {
+            graphics.draw_text(position, Color::DARK_GRAY, &font.layout_text(&label, scale, TextOptions::new()));
+        }
+    }
+
+    /// Label text "(x, y)" of every vertice and where to draw it, empty for grids too large to label
+    pub fn vertice_labels(&self, window_size: (usize, usize)) -> Vec<(VerticeLoc, String, (f32, f32))> {
+        if !is_drawable(window_size) || self.vertex_count() > MAX_LABELLED_VERTICES {
+            return Vec::new();
+        }
+        let offset = self.vertice_radius(window_size) * 1.5;
+        (0..self.pheromone_graph.height)
+            .flat_map(|y| (0..self.pheromone_graph.width).map(move |x| VerticeLoc::new(x, y)))
+            .map(|vertice| {
+                let (x, y) = self.vertice_coordinates(window_size, vertice);
+                (vertice, format!("({}, {})", vertice.x(), vertice.y()), (x + offset, y + offset))
+            })
+            .collect()
+    }
+
+    /// Draw every edge with an opacity given by its normalized pheromone
+    #[allow(dead_code)]
+    pub fn render_pheromones(&self, window_size: (usize, usize), graphics: &mut Graphics2D, mode: NormalizeMode) {
+        if !is_drawable(window_size) {
+            return;
+        }
+        for ((v0, v1), intensity) in self.normalized_pheromones(mode) {
+            graphics.draw_line(
+                self.vertice_coordinates(window_size, v0),
+                self.vertice_coordinates(window_size, v1),
+                1.0,
+                Color::from_rgba(0.0, 0.0, 1.0, intensity)
+            );
+        }
+    }
+
+    /// Draw the layers picked by style, cost gives the cost of every cell for the heatmap
+    #[allow(dead_code)]
+    pub fn render_layers<F: Fn(VerticeLoc) -> f32>(&self, window_size: (usize, usize), graphics: &mut Graphics2D,
+        style: &RenderStyle, cost: F) {
+        if !is_drawable(window_size) {
+            return;
+        }
+        if style.layer != RenderLayer::Pheromone {
+            let half = self.cell_spacing(window_size) / 2.0;
+            for (vertice, color) in self.cost_colors(cost) {
+                let (x, y) = self.vertice_coordinates(window_size, vertice);
+                graphics.draw_rectangle(Rectangle::from_tuples((x - half, y - half), (x + half, y + half)), color);
+            }
+        }
+        if style.layer != RenderLayer::Cost {
+            self.render(window_size, graphics);
+        }
+    }
+
+    /// Heatmap color of every free cell from white for the cheapest to red for the most
+    /// expensive one, cells with a non-finite cost are left out
+    pub fn cost_colors<F: Fn(VerticeLoc) -> f32>(&self, cost: F) -> Vec<(VerticeLoc, Color)> {
+        let costs: Vec<(VerticeLoc, f32)> = (0..self.pheromone_graph.height)
+            .flat_map(|y| (0..self.pheromone_graph.width).map(move |x| VerticeLoc::new(x, y)))
+            .filter(|vertice| !self.is_obstacle(*vertice))
+            .map(|vertice| (vertice, cost(vertice)))
+            .filter(|(_, cost)| cost.is_finite())
+            .collect();
+        let min = costs.iter().map(|(_, cost)| *cost).fold(f32::INFINITY, f32::min);
+        let max = costs.iter().map(|(_, cost)| *cost).fold(f32::NEG_INFINITY, f32::max);
+        let range = max - min;
+        costs.into_iter()
+            .map(|(vertice, cost)| {
+                let heat = if range > 0.0 { (cost - min) / range } else { 0.0 };
+                (vertice, Color::from_rgb(1.0, 1.0 - heat, 1.0 - heat))
+            })
+            .collect()
+    }
+
+    /// Draw every edge a source deposited on tinted by the colors of the sources, blended by
+    /// their share of the pheromone. colors[i] is the color of source i.
+    #[allow(dead_code)]
+    pub fn render_sources(&self, window_size: (usize, usize), graphics: &mut Graphics2D, colors: &[Color]) {
+        if !is_drawable(window_size) || colors.is_empty() {
+            return;
+        }
+        for (&(v0, v1), contributions) in &self.source_pheromone {
+            let total: f32 = contributions.iter().sum();
+            if total <= 0.0 {
+                continue;
+            }
+            let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+            for (source, contribution) in contributions.iter().enumerate() {
+                let color = colors[source % colors.len()];
+                let share = contribution / total;
+                r += color.r() * share;
+                g += color.g() * share;
+                b += color.b() * share;
+            }
+            graphics.draw_line(
+                self.vertice_coordinates(window_size, v0),
+                self.vertice_coordinates(window_size, v1),
+                1.0,
+                Color::from_rgb(r, g, b)
+            );
+        }
+    }
+
+    /// Only redraw the vertices and edges that changed since the previous call,
+    /// the caller is responsible for keeping the rest of the frame around. Nothing is drawn
+    /// or forgotten while the window has a zero dimension.
+    #[allow(dead_code)]
+    pub fn render_incremental(&mut self, window_size: (usize, usize), graphics: &mut Graphics2D) {
+        if !is_drawable(window_size) {
+            return;
+        }
+        let r = self.vertice_radius(window_size);
+
+        for (v0, v1) in self.changed_edges() {
+            let pheromone = self.pheromone_graph.get_edg_value(v0, v1);
+            graphics.draw_line(
+                self.vertice_coordinates(window_size, v0),
+                self.vertice_coordinates(window_size, v1),
+                1.0,
+                Color::from_rgba(0.0, 0.0, 1.0, pheromone / (pheromone + 1.0))
+            );
+        }
+
+        for vertice in self.changed_vertices() {
+            graphics.draw_circle(self.vertice_coordinates(window_size, vertice), r, self.vertice_color(vertice));
+        }
+
+        self.dirty_edges.clear();
+        self.dirty_vertices.clear();
+    }
+
+    fn vertice_radius(&self, window_size: (usize, usize)) -> f32 {
+        self.cell_spacing(window_size) / 20.0
+    }
+
+    /// Smallest pixel distance between two orthogonally neighbouring vertices
+    pub fn cell_spacing(&self, window_size: (usize, usize)) -> f32 {
+        let x_spacing = window_size.0 as f32 / self.pheromone_graph.width as f32;
+        let y_spacing = (window_size.1 as f32 - x_spacing) / (self.pheromone_graph.height - 1) as f32;
+        if x_spacing < y_spacing { x_spacing } else { y_spacing }
+    }
+
+    fn vertice_color(&self, vertice: VerticeLoc) -> Color {
+        if self.is_obstacle(vertice) { Color::BLACK } else { Color::GRAY }
+    }
+
+    /// Pixel position of vertice in a window of window_size
+    pub fn get_vertice_coordinates(&self, window_size: (usize, usize), vertice: VerticeLoc) -> Result<(f32, f32), AcoError> {
+        if !is_drawable(window_size) {
+            return Err(AcoError::InvalidWindowSize(window_size.0, window_size.1));
+        }
+        self.validated(vertice).map(|vertice| self.vertice_coordinates(window_size, vertice))
+    }
+
+    /// Vertice drawn closest to pixel, the inverse of get_vertice_coordinates.
+    /// None if the pixel lies outside the area the grid is drawn in.
+    pub fn pixel_to_vertice(&self, window_size: (usize, usize), pixel: (f32, f32)) -> Option<VerticeLoc> {
+        if !is_drawable(window_size) {
+            return None;
+        }
+        let x_spacing = window_size.0 as f32 / self.pheromone_graph.width as f32;
+        let y_spacing = (window_size.1 as f32 - x_spacing) / (self.pheromone_graph.height - 1) as f32;
+        let offs = x_spacing / 2.0;
+        let x = ((pixel.0 - offs) / x_spacing).round();
+        let y = if self.pheromone_graph.height > 1 { ((pixel.1 - offs) / y_spacing).round() } else { 0.0 };
+        let fits = |index: f32, size: usize| index >= 0.0 && index < size as f32;
+        if fits(x, self.pheromone_graph.width) && fits(y, self.pheromone_graph.height) {
+            Some(VerticeLoc::new(x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    fn vertice_coordinates(&self, window_size: (usize, usize), vertice: VerticeLoc) -> (f32, f32) {
+        let x_spacing = window_size.0 as f32 / self.pheromone_graph.width as f32;
+        let y_spacing = (window_size.1 as f32 - x_spacing) / (self.pheromone_graph.height - 1) as f32;
+        let x_offs = x_spacing / 2.0;
+        let y_offs = x_offs;
+        let x = x_offs + vertice.x() as f32 * x_spacing;
+        let y = y_offs + vertice.y() as f32 * y_spacing;
+        (x, y)
+    }
+}
+
+/// Visible part of the window in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub min: (f32, f32),
+    pub max: (f32, f32)
+}
+
+impl Viewport {
+    /// The whole window
+    pub fn from_window(window_size: (usize, usize)) -> Self {
+        Viewport {min: (0.0, 0.0), max: (window_size.0 as f32, window_size.1 as f32)}
+    }
+}
+
+/// Entry in the A* open set, ordered so that BinaryHeap pops the lowest estimate first
+#[derive(PartialEq)]
+struct OpenVertice {
+    estimate: f32,
+    vertice: VerticeLoc
+}
+
+impl Eq for OpenVertice {}
+
+impl Ord for OpenVertice {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.vertice.cmp(&other.vertice))
+    }
+}
+
+impl PartialOrd for OpenVertice {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Map value onto 0..1 relative to the full set of values, which must be sorted ascending
+fn normalize(value: f32, sorted: &[f32], mode: NormalizeMode) -> f32 {
+    match mode {
+        NormalizeMode::Linear => normalize_linear(value, sorted.last().copied().unwrap_or(0.0)),
+        NormalizeMode::Percentile => {
+            let below = sorted.partition_point(|other| *other < value);
+            let equal = sorted[below..].partition_point(|other| *other <= value);
+            normalize_percentile(below, equal, sorted.len())
+        }
+    }
+}
+
+fn normalize_linear(value: f32, max: f32) -> f32 {
+    if max <= 0.0 { 0.0 } else { value / max }
+}
+
+/// Fraction of the count values ranked below a value, using the mid-rank so that ties
+/// end up at the same fraction
+fn normalize_percentile(below: usize, equal: usize, count: usize) -> f32 {
+    if count < 2 {
+        return 1.0;
+    }
+    let rank = below as f32 + equal.saturating_sub(1) as f32 / 2.0;
+    rank / (count - 1) as f32
+}
+
+/// A window with a zero dimension has nowhere to draw, e.g. while it is minimized
+fn is_drawable(window_size: (usize, usize)) -> bool {
+    window_size.0 > 0 && window_size.1 > 0
+}
+
+fn validate_evaporation_rate(evaporation_rate: f32) -> Result<f32, AcoError> {
+    if (0.0..1.0).contains(&evaporation_rate) {
+        Ok(evaporation_rate)
+    } else {
+        Err(AcoError::InvalidEvaporationRate(evaporation_rate))
+    }
+}
+
+/// Order the end points of an undirected edge so that (v0, v1) and (v1, v0) map to the same key
+fn edge_key(v0: VerticeLoc, v1: VerticeLoc) -> (VerticeLoc, VerticeLoc) {
+    if v0 <= v1 { (v0, v1) } else { (v1, v0) }
+}
+
+#[test]
+fn test_simplify_path_staircase() {
+    let map = ACOMap::try_new(5, 5, 0.5).unwrap();
+    let path: Vec<VerticeLoc> = [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2), (3, 2), (3, 3)]
+        .into_iter()
+        .map(VerticeLoc::from)
+        .collect();
+    assert_eq!(map.simplify_path(&path), vec![VerticeLoc::new(0, 0), VerticeLoc::new(3, 3)]);
+}
+
+#[test]
+fn test_simplify_path_around_obstacle() {
+    let mut map = ACOMap::try_new(5, 5, 0.5).unwrap();
+    map.set_obstacle(VerticeLoc::new(2, 0)).unwrap();
+    map.set_obstacle(VerticeLoc::new(2, 1)).unwrap();
+    map.set_obstacle(VerticeLoc::new(2, 2)).unwrap();
+    let path: Vec<VerticeLoc> = [(0, 0), (1, 1), (1, 2), (2, 3), (3, 2), (3, 1), (4, 0)]
+        .into_iter()
+        .map(VerticeLoc::from)
+        .collect();
+
+    let simplified = map.simplify_path(&path);
+    assert!(simplified.len() > 2);
+    assert_eq!(simplified.first(), Some(&VerticeLoc::new(0, 0)));
+    assert_eq!(simplified.last(), Some(&VerticeLoc::new(4, 0)));
+    simplified.windows(2).for_each(|segment| {
+        assert!(map.line_of_sight(segment[0], segment[1]));
+    });
+}
+
+#[test]
+fn test_min_likelihood_keeps_neighbours_alive() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let center = VerticeLoc::new(1, 1);
+    map.pheromone_graph.fill(0.0);
+    map.pheromone_graph.set_edg_value(center, VerticeLoc::new(2, 1), 1.0);
+    map.set_obstacle(VerticeLoc::new(0, 0)).unwrap();
+
+    let dead = map.get_neighbours(center)
+        .into_iter()
+        .filter(|neighbour| map.get_likelyhood_factor(center, *neighbour) <= 0.0)
+        .count();
+    assert_eq!(dead, 6);
+
+    map.set_min_likelihood(1e-3);
+    let neighbours = map.get_neighbours(center);
+    assert_eq!(neighbours.len(), 7);
+    neighbours.iter().for_each(|neighbour| {
+        assert!(map.get_likelyhood_factor(center, *neighbour) > 0.0);
+    });
+    assert!(!neighbours.contains(&VerticeLoc::new(0, 0)));
+}
+
+#[test]
+fn test_trail_concentration_uniform_field() {
+    let map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let n_edges = map.edges().len();
+    assert_eq!(n_edges, 20);
+    assert_eq!(map.trail_concentration(0.0), n_edges);
+    assert_eq!(map.trail_concentration(1.0), 0);
+}
+
+#[test]
+fn test_changed_edges_after_deposit() {
+    let mut map = ACOMap::try_new(4, 4, 0.5).unwrap();
+    assert!(map.changed_edges().is_empty());
+
+    let path = [VerticeLoc::new(0, 0), VerticeLoc::new(1, 1), VerticeLoc::new(1, 2)];
+    map.deposit_pheromone(&path, 0.5).unwrap();
+    assert_eq!(map.changed_edges(), vec![
+        (VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)),
+        (VerticeLoc::new(1, 1), VerticeLoc::new(1, 2))
+    ]);
+    assert!(map.changed_vertices().is_empty());
+
+    map.dirty_edges.clear();
+    map.deposit_pheromone(&[VerticeLoc::new(3, 3), VerticeLoc::new(2, 3)], 0.5).unwrap();
+    assert_eq!(map.changed_edges(), vec![(VerticeLoc::new(2, 3), VerticeLoc::new(3, 3))]);
+
+    map.dirty_edges.clear();
+    map.evaporate();
+    assert_eq!(map.changed_edges().len(), map.edge_count());
+}
+
+#[test]
+fn test_try_move() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    map.set_obstacle(VerticeLoc::new(2, 2)).unwrap();
+    map.forbid_edge(VerticeLoc::new(0, 1), VerticeLoc::new(1, 1)).unwrap();
+
+    assert_eq!(map.try_move(VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)), Ok(()));
+    assert_eq!(map.try_move(VerticeLoc::new(2, 1), VerticeLoc::new(3, 1)), Err(MoveError::OutOfBounds));
+    assert_eq!(map.try_move(VerticeLoc::new(1, 1), VerticeLoc::new(2, 2)), Err(MoveError::Obstacle));
+    assert_eq!(map.try_move(VerticeLoc::new(0, 0), VerticeLoc::new(2, 0)), Err(MoveError::NotAdjacent));
+    assert_eq!(map.try_move(VerticeLoc::new(1, 1), VerticeLoc::new(0, 1)), Err(MoveError::Forbidden));
+    assert!(!map.get_neighbours(VerticeLoc::new(1, 1)).contains(&VerticeLoc::new(0, 1)));
+
+    map.set_connectivity(Connectivity::Four);
+    assert_eq!(map.try_move(VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)), Err(MoveError::NotAdjacent));
+}
+
+#[test]
+fn test_is_adjacent() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let (v00, v10, v11) = (VerticeLoc::new(0, 0), VerticeLoc::new(1, 0), VerticeLoc::new(1, 1));
+
+    assert!(map.is_adjacent(v00, v10));
+    assert!(map.is_adjacent(v00, v11));
+    assert!(!map.is_adjacent(v00, VerticeLoc::new(2, 0)));
+    assert!(!map.is_adjacent(v00, v00));
+    assert!(!map.is_adjacent(VerticeLoc::new(2, 2), VerticeLoc::new(3, 3)));
+
+    map.set_connectivity(Connectivity::Four);
+    assert!(map.is_adjacent(v00, v10));
+    assert!(!map.is_adjacent(v00, v11));
+
+    map.forbid_edge(v10, v00).unwrap();
+    assert!(!map.is_adjacent(v00, v10));
+
+    // Every entry point that takes a path rejects the same jumps
+    let jump = [v10, VerticeLoc::new(2, 1)];
+    assert_eq!(map.path_cost(&jump), f32::INFINITY);
+    assert_eq!(map.deposit_pheromone(&jump, 1.0), Err(AcoError::InvalidEdge(jump[0], jump[1])));
+    assert!(map.changed_edges().is_empty());
+    let edges = HashMap::from([((v10, VerticeLoc::new(1, 2)), 2.0)]);
+    assert!(matches!(ACOMap::with_pheromone_matrix(3, 3, 0.5, &edges), Err(AcoError::InvalidEdge(_, _))));
+}
+
+#[test]
+fn test_normalized_pheromone_percentile_spreads_skewed_field() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let edges = map.edges();
+    edges.iter().enumerate().for_each(|(i, (v0, v1))| {
+        map.pheromone_graph.set_edg_value(*v0, *v1, 2.0_f32.powi(i as i32));
+    });
+
+    let linear: Vec<f32> = edges.iter()
+        .map(|(v0, v1)| map.normalized_pheromone(*v0, *v1, NormalizeMode::Linear))
+        .collect();
+    let percentile: Vec<f32> = edges.iter()
+        .map(|(v0, v1)| map.normalized_pheromone(*v0, *v1, NormalizeMode::Percentile))
+        .collect();
+
+    assert_eq!(linear.iter().filter(|value| **value < 0.01).count(), edges.len() - 7);
+    assert_eq!(percentile.iter().cloned().fold(1.0, f32::min), 0.0);
+    assert_eq!(percentile.iter().cloned().fold(0.0, f32::max), 1.0);
+    let mean = percentile.iter().sum::<f32>() / percentile.len() as f32;
+    assert!((mean - 0.5).abs() < 1e-3);
+    assert!(percentile.iter().filter(|value| **value < 0.01).count() == 1);
+}
+
+#[test]
+fn test_normalized_pheromones_match_single_edge_lookup() {
+    let mut map = ACOMap::try_new(4, 3, 0.5).unwrap();
+    map.edges().iter().enumerate().for_each(|(i, (v0, v1))| {
+        map.pheromone_graph.set_edg_value(*v0, *v1, (i % 4) as f32);
+    });
+    for mode in [NormalizeMode::Linear, NormalizeMode::Percentile] {
+        map.normalized_pheromones(mode).into_iter().for_each(|((v0, v1), normalized)| {
+            assert_eq!(normalized, map.normalized_pheromone(v0, v1, mode));
+        });
+    }
+}
+
+#[test]
+fn test_step_ant_resets_stuck_ant_within_budget() {
+    let mut map = ACOMap::try_new(4, 4, 0.5).unwrap();
+    [(2, 0), (0, 1), (1, 1), (2, 1)].into_iter().for_each(|obstacle| map.set_obstacle(obstacle.into()).unwrap());
+
+    let start = VerticeLoc::new(0, 0);
+    let mut ant = AntState::new(start);
+    assert_eq!(map.step_ant(&mut ant, 1).unwrap(), StepOutcome::Moved(VerticeLoc::new(1, 0)));
+
+    // The pocket is a dead end, one backtrack is not enough to get anywhere
+    assert_eq!(map.step_ant(&mut ant, 1).unwrap(), StepOutcome::Stuck);
+    assert_eq!(ant.current, start);
+    assert_eq!(ant.path, vec![start]);
+    assert!(ant.exclusions.is_empty());
+
+    // Boxed in completely, resets no matter how large the budget is
+    map.set_obstacle(VerticeLoc::new(1, 0)).unwrap();
+    assert_eq!(map.step_ant(&mut ant, 1000).unwrap(), StepOutcome::Reset);
+    assert_eq!(ant.current, start);
+}
+
+#[test]
+fn test_to_dot() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let dot = map.to_dot(0.0);
+    assert!(dot.starts_with("graph aco {"));
+    assert_eq!(dot.lines().filter(|line| line.contains("[pos=")).count(), 9);
+    assert_eq!(dot.lines().filter(|line| line.contains(" -- ")).count(), 20);
+
+    map.deposit_pheromone(&[VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)], 1.0).unwrap();
+    let dot = map.to_dot(1.5);
+    assert_eq!(dot.lines().filter(|line| line.contains(" -- ")).count(), 1);
+    assert!(dot.contains("\"0_0\" -- \"1_1\" [weight=2"));
+}
+
+#[test]
+fn test_find_path_reaches_goal() {
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    map.set_iterations(10);
+    let start = VerticeLoc::new(0, 0);
+    let goal = VerticeLoc::new(4, 4);
+
+    let result = map.find_path(start, goal).unwrap();
+    assert_eq!(result.path.first(), Some(&start));
+    assert_eq!(result.path.last(), Some(&goal));
+    result.path.windows(2).for_each(|edge| assert_eq!(map.try_move(edge[0], edge[1]), Ok(())));
+    assert_eq!(result.cost, map.path_cost(&result.path));
+}
+
+#[test]
+fn test_find_path_from_weighted_starts() {
+    let a = VerticeLoc::new(0, 0);
+    let b = VerticeLoc::new(0, 4);
+    let starts = [(a, 3.0), (b, 1.0)];
+
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    map.set_iterations(5);
+    let result = map.find_path_from(&starts, VerticeLoc::new(4, 2)).unwrap();
+    assert!(result.path[0] == a || result.path[0] == b);
+    assert_eq!(map.find_path_from(&[(a, 1.0)], VerticeLoc::new(4, 2)).unwrap().path[0], a);
+}
+
+#[test]
+fn test_random_obstacles_keep_start_and_goal_connected() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let mut map = ACOMap::try_new(20, 20, 0.5).unwrap();
+    let start = VerticeLoc::new(0, 0);
+    let goal = VerticeLoc::new(19, 19);
+
+    map.random_obstacles(0.3, start, goal, &mut rng).unwrap();
+    assert!(!map.is_obstacle(start));
+    assert!(!map.is_obstacle(goal));
+    assert!(map.is_reachable(start, goal));
+    let density = map.obstacles.len() as f32 / 400.0;
+    assert!(density > 0.2 && density < 0.4, "density = {}", density);
+
+    assert_eq!(map.random_obstacles(1.0, start, goal, &mut rng), Err(AcoError::InvalidDensity(1.0)));
+    assert_eq!(map.random_obstacles(-0.1, start, goal, &mut rng), Err(AcoError::InvalidDensity(-0.1)));
+    assert_eq!(map.random_obstacles(0.3, start, VerticeLoc::new(20, 0), &mut rng), Err(AcoError::OutOfBounds));
+}
+
+#[test]
+fn test_random_obstacles_four_connectivity_corridor() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let mut map = ACOMap::try_new(20, 20, 0.5).unwrap();
+    map.set_connectivity(Connectivity::Four);
+    let (start, goal) = (VerticeLoc::new(0, 0), VerticeLoc::new(19, 13));
+
+    // Dense enough that every layout cuts them apart and the corridor has to be carved
+    map.random_obstacles(0.95, start, goal, &mut rng).unwrap();
+    assert!(map.is_reachable(start, goal));
+    assert!(map.astar(start, goal).unwrap().path.is_valid(&map));
+
+    let mut map = ACOMap::try_new(2, 1, 0.5).unwrap();
+    map.forbid_edge(VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)).unwrap();
+    assert_eq!(map.random_obstacles(0.5, VerticeLoc::new(0, 0), VerticeLoc::new(1, 0), &mut rng),
+        Err(AcoError::Unreachable(VerticeLoc::new(1, 0))));
+}
+
+#[test]
+fn test_find_path_moving_goal() {
+    let mut map = ACOMap::try_new(10, 5, 0.3).unwrap();
+    // A single iteration towards the last goal is not always enough without a fixed seed
+    map.set_seed(Some(1));
+    let start = VerticeLoc::new(0, 2);
+    let mut goals = Vec::new();
+
+    let result = map.find_path_moving_goal(start, |iteration| {
+        let goal = VerticeLoc::new(5 + iteration, 2);
+        goals.push(goal);
+        goal
+    }, 5).unwrap();
+
+    assert_eq!(goals, (5..10).map(|x| VerticeLoc::new(x, 2)).collect::<Vec<_>>());
+    assert_eq!(result.path.first(), Some(&start));
+    assert_eq!(result.path.last(), Some(&VerticeLoc::new(9, 2)));
+    assert_eq!(map.goal(), None);
+
+    // The last goal is walled in, the path to the goal before it is kept
+    let wall = [VerticeLoc::new(8, 1), VerticeLoc::new(8, 2), VerticeLoc::new(8, 3), VerticeLoc::new(9, 1), VerticeLoc::new(9, 3)];
+    wall.iter().for_each(|vertice| map.set_obstacle(*vertice).unwrap());
+    let result = map.find_path_moving_goal(start, |iteration| {
+        if iteration < 4 { VerticeLoc::new(6, 2) } else { VerticeLoc::new(9, 2) }
+    }, 5).unwrap();
+    assert_eq!(result.path.last(), Some(&VerticeLoc::new(6, 2)));
+}
+
+#[test]
+fn test_cost_history_restarts_with_goal() {
+    let mut map = ACOMap::try_new(10, 5, 0.3).unwrap();
+    map.set_seed(Some(2));
+    map.set_record_history(true);
+    map.find_path(VerticeLoc::new(0, 2), VerticeLoc::new(9, 2));
+    assert!(!map.cost_history().is_empty());
+    drop(AntColony::new(&mut map, VerticeLoc::new(0, 2), VerticeLoc::new(9, 2)));
+    assert!(map.cost_history().is_empty());
+
+    // The close goal is found quickly, the far one that follows must not start below it
+    map.find_path_moving_goal(VerticeLoc::new(0, 2), |iteration| {
+        if iteration < 6 { VerticeLoc::new(2, 2) } else { VerticeLoc::new(9, 2) }
+    }, 10);
+    let history = map.cost_history();
+    assert_eq!(history.len(), 4);
+    assert!(history.windows(2).all(|costs| costs[1] <= costs[0]));
+}
+
+#[test]
+fn test_colony_restores_goal() {
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    let goal = VerticeLoc::new(4, 4);
+    map.find_path(VerticeLoc::new(0, 0), goal).unwrap();
+    assert_eq!(map.goal(), None);
+    map.find_path_anytime(VerticeLoc::new(0, 0), goal).for_each(drop);
+    assert_eq!(map.goal(), None);
+
+    map.set_goal(Some(VerticeLoc::new(2, 2))).unwrap();
+    map.find_path_any_goal(VerticeLoc::new(0, 0), &[goal]).unwrap();
+    map.solve_with_timeout_and_callback(VerticeLoc::new(0, 0), goal, std::time::Duration::from_millis(10), |_| ());
+    assert_eq!(map.goal(), Some(VerticeLoc::new(2, 2)));
+}
+
+#[test]
+fn test_pheromone_centroid() {
+    let mut map = ACOMap::try_new(10, 10, 0.5).unwrap();
+    assert_eq!(map.pheromone_centroid(), (4.5, 4.5));
+
+    let corner = [VerticeLoc::new(9, 9), VerticeLoc::new(8, 9), VerticeLoc::new(8, 8), VerticeLoc::new(9, 8), VerticeLoc::new(9, 9)];
+    map.deposit_pheromone(&corner, 1000.0).unwrap();
+    let (x, y) = map.pheromone_centroid();
+    assert!(x > 8.0 && y > 8.0, "centroid = ({}, {})", x, y);
+}
+
+#[test]
+fn test_find_path_isolated_start() {
+    let mut map = ACOMap::try_new(5, 5, 0.5).unwrap();
+    let start = VerticeLoc::new(0, 0);
+    let goal = VerticeLoc::new(4, 4);
+    [(1, 0), (0, 1), (1, 1)].into_iter().for_each(|obstacle| map.set_obstacle(obstacle.into()).unwrap());
+
+    assert_eq!(map.neighbour_count(start), 0);
+    assert_eq!(map.try_find_path(start, goal), Err(AcoError::StartIsolated));
+    assert_eq!(map.find_path(start, goal), None);
+    // Nothing ran, so nothing evaporated
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(3, 3), goal), 1.0);
+
+    map.set_obstacle(start).unwrap();
+    assert_eq!(map.try_find_path(start, goal), Err(AcoError::StartIsolated));
+    assert_eq!(map.try_find_path(start, VerticeLoc::new(5, 0)), Err(AcoError::OutOfBounds));
+}
+
+#[test]
+fn test_astar_optimal_cost() {
+    let mut map = ACOMap::try_new(5, 5, 0.5).unwrap();
+    let result = map.astar(VerticeLoc::new(0, 0), VerticeLoc::new(4, 2)).unwrap();
+    assert!((result.cost - (2.0 + 2.0 * std::f32::consts::SQRT_2)).abs() < 1e-4);
+
+    // A wall with a single gap at the bottom
+    [(2, 0), (2, 1), (2, 2), (2, 3)].into_iter().for_each(|obstacle| map.set_obstacle(obstacle.into()).unwrap());
+    let result = map.astar(VerticeLoc::new(0, 0), VerticeLoc::new(4, 0)).unwrap();
+    assert!(result.path.contains(VerticeLoc::new(2, 4)));
+    result.path.windows(2).for_each(|edge| assert_eq!(map.try_move(edge[0], edge[1]), Ok(())));
+}
+
+#[test]
+fn test_quality_ratio() {
+    let mut map = ACOMap::try_new(6, 6, 0.2).unwrap();
+    map.set_obstacle(VerticeLoc::new(2, 2)).unwrap();
+    map.set_obstacle(VerticeLoc::new(3, 3)).unwrap();
+    let ratio = map.quality_ratio(VerticeLoc::new(0, 0), VerticeLoc::new(5, 5)).unwrap();
+    assert!(ratio.is_finite());
+    assert!(ratio >= 1.0);
+}
+
+#[test]
+fn test_neighbours_sorted_by_pheromone() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let center = VerticeLoc::new(1, 1);
+    map.deposit_pheromone(&[center, VerticeLoc::new(2, 2)], 3.0).unwrap();
+    map.deposit_pheromone(&[center, VerticeLoc::new(0, 1)], 2.0).unwrap();
+    map.deposit_pheromone(&[center, VerticeLoc::new(1, 0)], 1.0).unwrap();
+
+    let sorted = map.neighbours_sorted_by_pheromone(center);
+    assert_eq!(sorted.len(), 8);
+    assert_eq!(&sorted[..3], &[(VerticeLoc::new(2, 2), 4.0), (VerticeLoc::new(0, 1), 3.0), (VerticeLoc::new(1, 0), 2.0)]);
+    sorted.windows(2).for_each(|pair| assert!(pair[0].1 >= pair[1].1));
+    // Remaining ties come in coordinate order
+    assert_eq!(sorted[3].0, VerticeLoc::new(0, 0));
+    assert_eq!(sorted[7].0, VerticeLoc::new(2, 1));
+}
+
+#[test]
+fn test_step_all_ants_finishes() {
+    let mut map = ACOMap::try_new(6, 6, 0.5).unwrap();
+    let mut ants = vec![
+        AntState::with_goal(VerticeLoc::new(0, 0), VerticeLoc::new(5, 5)),
+        AntState::with_goal(VerticeLoc::new(5, 0), VerticeLoc::new(0, 5)),
+        AntState::new(VerticeLoc::new(2, 2))
+    ];
+
+    let mut steps = 0;
+    while map.step_all_ants(&mut ants) {
+        steps += 1;
+        assert!(steps < 36, "ants still active after {} steps", steps);
+    }
+    assert!(ants.iter().all(|ant| ant.done));
+    ants.iter().for_each(|ant| assert!(ant.route().len() <= 36));
+    assert!(!map.step_all_ants(&mut ants));
+
+    // A corridor leaves one way to go, the ant walks exactly max_path_len vertices
+    let mut map = ACOMap::try_new(6, 1, 0.5).unwrap();
+    map.set_max_path_len(4);
+    let mut ants = vec![AntState::new(VerticeLoc::new(0, 0))];
+    while map.step_all_ants(&mut ants) {}
+    assert_eq!(ants[0].route(), (0..4).map(|x| VerticeLoc::new(x, 0)).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_to_pgm() {
+    let mut map = ACOMap::try_new(4, 3, 0.5).unwrap();
+    map.deposit_pheromone(&[VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)], 10.0).unwrap();
+    let pgm = map.to_pgm();
+
+    let header = b"P5\n4 3\n255\n";
+    assert_eq!(&pgm[..header.len()], header);
+    let pixels = &pgm[header.len()..];
+    assert_eq!(pixels.len(), 12);
+    assert_eq!(pixels.iter().max(), Some(&255));
+    assert!(pixels[0] == 255 || pixels[1] == 255);
+}
+
+#[test]
+fn test_reachable_set_split_by_wall() {
+    let mut map = ACOMap::try_new(6, 4, 0.5).unwrap();
+    (0..4).for_each(|y| map.set_obstacle(VerticeLoc::new(3, y)).unwrap());
+
+    let reachable = map.reachable_set(VerticeLoc::new(0, 0));
+    assert_eq!(reachable.len(), 12);
+    assert!(reachable.iter().all(|vertice| vertice.x() < 3));
+    assert!(!map.is_reachable(VerticeLoc::new(0, 0), VerticeLoc::new(5, 3)));
+    assert!(map.reachable_set(VerticeLoc::new(3, 0)).is_empty());
+}
+
+#[test]
+fn test_reachable_set_respects_connectivity() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    map.set_obstacle(VerticeLoc::new(1, 0)).unwrap();
+    map.set_obstacle(VerticeLoc::new(0, 1)).unwrap();
+    assert_eq!(map.reachable_set(VerticeLoc::new(0, 0)).len(), 7);
+
+    map.set_connectivity(Connectivity::Four);
+    assert_eq!(map.reachable_set(VerticeLoc::new(0, 0)), HashSet::from([VerticeLoc::new(0, 0)]));
+
+    map.set_connectivity(Connectivity::Eight);
+    map.forbid_edge(VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)).unwrap();
+    assert_eq!(map.reachable_set(VerticeLoc::new(0, 0)).len(), 1);
+}
+
+#[test]
+fn test_find_path_waypoints() {
+    let mut map = ACOMap::try_new(7, 7, 0.2).unwrap();
+    map.set_iterations(10);
+    let start = VerticeLoc::new(0, 0);
+    let waypoint = VerticeLoc::new(6, 0);
+    let goal = VerticeLoc::new(6, 6);
+
+    let result = map.find_path_waypoints(start, &[waypoint], goal).unwrap();
+    assert_eq!(result.path.first(), Some(&start));
+    assert_eq!(result.path.last(), Some(&goal));
+    let index = result.path.iter().position(|vertice| *vertice == waypoint).unwrap();
+    assert!(index > 0 && index < result.path.len() - 1);
+    result.path.windows(2).for_each(|edge| assert_eq!(map.try_move(edge[0], edge[1]), Ok(())));
+    assert!((result.cost - map.path_cost(&result.path)).abs() < 1e-3);
+
+    assert_eq!(map.find_path_waypoints(start, &[VerticeLoc::new(7, 0)], goal), Err(AcoError::OutOfBounds));
+    [(5, 0), (5, 1), (6, 1)].into_iter().for_each(|obstacle| map.set_obstacle(obstacle.into()).unwrap());
+    assert_eq!(map.find_path_waypoints(start, &[waypoint], goal), Err(AcoError::Unreachable(waypoint)));
+}
+
+#[test]
+fn test_octile_distance() {
+    let map = ACOMap::try_new(10, 10, 0.5).unwrap();
+    let origin = VerticeLoc::new(2, 2);
+    assert_eq!(map.octile_distance(origin, VerticeLoc::new(6, 6)), 4.0 * map.diagonal_cost());
+    assert_eq!(map.octile_distance(VerticeLoc::new(6, 6), origin), 4.0 * map.diagonal_cost());
+    assert_eq!(map.octile_distance(origin, VerticeLoc::new(2, 7)), 5.0 * map.straight_cost());
+    assert_eq!(map.octile_distance(origin, VerticeLoc::new(5, 9)), 3.0 * map.diagonal_cost() + 4.0 * map.straight_cost());
+    assert_eq!(map.octile_distance(origin, origin), 0.0);
+}
+
+#[test]
+fn test_global_best_reinforcement_dominates() {
+    let mut map = ACOMap::try_new(5, 5, 0.5).unwrap();
+    map.set_seed(Some(8));
+    map.set_reinforcement(Reinforcement::GlobalBest);
+    map.set_global_best_weight(1.0);
+    let best = AntColony::new(&mut map, VerticeLoc::new(0, 0), VerticeLoc::new(4, 4))
+        .run(30)
+        .cloned()
+        .unwrap();
+
+    let on_path = |v0: VerticeLoc, v1: VerticeLoc| best.path.windows(2)
+        .any(|edge| (edge[0], edge[1]) == (v0, v1) || (edge[1], edge[0]) == (v0, v1));
+    let weakest_on_path = best.path.windows(2)
+        .map(|edge| map.pheromone_graph.get_edg_value(edge[0], edge[1]))
+        .fold(f32::MAX, f32::min);
+    let strongest_elsewhere = map.edges().into_iter()
+        .filter(|(v0, v1)| !on_path(*v0, *v1))
+        .map(|(v0, v1)| map.pheromone_graph.get_edg_value(v0, v1))
+        .fold(0.0, f32::max);
+    assert!(weakest_on_path > strongest_elsewhere, "{} <= {}", weakest_on_path, strongest_elsewhere);
+}
+
+#[test]
+fn test_try_new_errors() {
+    assert_eq!(ACOMap::try_new(0, 5, 0.1).err(), Some(AcoError::InvalidDimensions(0, 5)));
+    assert_eq!(ACOMap::try_new(5, 0, 0.1).err(), Some(AcoError::InvalidDimensions(5, 0)));
+    assert_eq!(ACOMap::try_new(5, 5, 1.5).err(), Some(AcoError::InvalidEvaporationRate(1.5)));
+    assert_eq!(ACOMap::try_new(5, 5, 1.0).err(), Some(AcoError::InvalidEvaporationRate(1.0)));
+    assert_eq!(ACOMap::try_new(5, 5, -0.1).err(), Some(AcoError::InvalidEvaporationRate(-0.1)));
+    assert!(ACOMap::try_new(5, 5, 0.1).is_ok());
+    assert!(ACOMapBuilder::new(5, 5).evaporation_rate(0.3).build().is_ok());
+}
+
+#[test]
+#[should_panic(expected = "ACOMap::new: width and height must be non-zero")]
+fn test_new_panics_on_zero_width() {
+    ACOMap::new(0, 5);
+}
+
+#[test]
+fn test_construct_path_accumulates_cost() {
+    let map = ACOMap::try_new(6, 6, 0.1).unwrap();
+    (0..50).for_each(|_| {
+        if let Some(result) = map.construct_path(VerticeLoc::new(0, 0), VerticeLoc::new(5, 3)) {
+            assert_eq!(result.cost, map.path_cost(&result.path));
+        }
+    });
+    let result = map.astar(VerticeLoc::new(0, 0), VerticeLoc::new(5, 3)).unwrap();
+    assert_eq!(result.cost, map.path_cost(&result.path));
+}
+
+#[test]
+fn test_grid_lines_count() {
+    let map = ACOMap::try_new(4, 3, 0.1).unwrap();
+    let lines = map.grid_lines((400, 300));
+    assert_eq!(lines.len(), 3 * 3 + 4 * 2);
+    lines.iter().for_each(|(p0, p1)| assert!(p0.0 == p1.0 || p0.1 == p1.1));
+}
+
+#[test]
+fn test_out_of_range_vertices_fail_uniformly() {
+    let mut map = ACOMap::try_new(4, 4, 0.1).unwrap();
+    let inside = VerticeLoc::new(0, 0);
+    let outside = VerticeLoc::new(4, 1);
+
+    assert_eq!(map.get_next_vertice(outside), Err(AcoError::OutOfBounds));
+    assert_eq!(map.get_vertice_coordinates((400, 400), outside), Err(AcoError::OutOfBounds));
+    assert_eq!(map.try_find_path(outside, inside), Err(AcoError::OutOfBounds));
+    assert_eq!(map.try_find_path(inside, outside), Err(AcoError::OutOfBounds));
+    assert_eq!(map.find_path_waypoints(inside, &[outside], VerticeLoc::new(3, 3)), Err(AcoError::OutOfBounds));
+    assert_eq!(map.random_obstacles(0.1, inside, outside, &mut rand::thread_rng()), Err(AcoError::OutOfBounds));
+
+    assert!(map.get_next_vertice(inside).unwrap().is_some());
+    assert!(map.get_vertice_coordinates((400, 400), inside).is_ok());
+}
+
+#[test]
+fn test_restart_on_stagnation_reseeds_best() {
+    // A single open row: the only path is found right away and can never improve
+    let mut map = ACOMap::try_new(4, 2, 0.1).unwrap();
+    (0..4).for_each(|x| map.set_obstacle(VerticeLoc::new(x, 1)).unwrap());
+    map.set_restart_on_stagnation(Some(3));
+
+    let mut colony = AntColony::new(&mut map, VerticeLoc::new(0, 0), VerticeLoc::new(3, 0));
+    while colony.stats().restarts == 0 && colony.stats().iterations < 10 {
+        colony.step();
+    }
+    assert_eq!(colony.stats().restarts, 1);
+    assert_eq!(colony.stats().iterations, 4);
+    let best = colony.best().cloned().unwrap();
+    drop(colony);
+
+    best.path.windows(2).for_each(|edge| {
+        assert_eq!(map.pheromone_graph.get_edg_value(edge[0], edge[1]), 1.0 + 1.0 / best.cost);
+    });
+}
+
+#[test]
+fn test_resize_keeps_overlapping_pheromone() {
+    let mut map = ACOMap::try_new(3, 3, 0.1).unwrap();
+    let path = [VerticeLoc::new(0, 0), VerticeLoc::new(1, 1), VerticeLoc::new(2, 1)];
+    map.deposit_pheromone(&path, 2.0).unwrap();
+    map.set_obstacle(VerticeLoc::new(2, 2)).unwrap();
+
+    map.resize(5, 4).unwrap();
+    assert_eq!(map.pheromone_graph.width, 5);
+    assert_eq!(map.pheromone_graph.height, 4);
+    assert_eq!(map.pheromone_graph.get_edg_value(path[0], path[1]), 3.0);
+    assert_eq!(map.pheromone_graph.get_edg_value(path[2], path[1]), 3.0);
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)), 1.0);
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(2, 1), VerticeLoc::new(3, 1)), 1.0);
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(4, 3), VerticeLoc::new(3, 3)), 1.0);
+    assert!(map.is_obstacle(VerticeLoc::new(2, 2)));
+
+    map.resize(2, 2).unwrap();
+    assert!(!map.is_obstacle(VerticeLoc::new(2, 2)));
+    assert_eq!(map.pheromone_graph.get_edg_value(path[0], path[1]), 3.0);
+    assert_eq!(map.resize(0, 2), Err(AcoError::InvalidDimensions(0, 2)));
+}
+
+#[test]
+fn test_resize_then_solve_longer_path() {
+    let mut map = ACOMap::try_new(2, 1, 0.1).unwrap();
+    map.set_seed(Some(5));
+    map.resize(6, 1).unwrap();
+    assert_eq!(map.max_path_len, 6);
+    let result = map.find_path(VerticeLoc::new(0, 0), VerticeLoc::new(5, 0)).unwrap();
+    assert_eq!(result.path.len(), 6);
+
+    map.set_max_path_len(4);
+    map.resize(8, 1).unwrap();
+    assert_eq!(map.max_path_len, 4);
+}
+
+#[test]
+fn test_threaded_construction_matches_serial() {
+    // An open map leaves the ants countless routes, they only agree if every ant
+    // draws from the same stream however they are spread over the threads
+    let mut map = ACOMap::try_new(8, 8, 0.1).unwrap();
+    let starts = vec![VerticeLoc::new(0, 0); 16];
+    let goal = VerticeLoc::new(7, 5);
+    let costs = |results: &[PathResult]| results.iter().map(|result| result.cost).collect::<Vec<f32>>();
+
+    map.set_threads(Some(1)).unwrap();
+    let serial = map.construct_paths(&starts, goal, &mut StdRng::seed_from_u64(7));
+    assert!(serial.len() > 1);
+    assert!(serial.iter().collect::<HashSet<&PathResult>>().len() > 1);
+    for threads in [Some(2), Some(4), None] {
+        map.set_threads(threads).unwrap();
+        assert_eq!(map.threads(), threads);
+        let threaded = map.construct_paths(&starts, goal, &mut StdRng::seed_from_u64(7));
+        assert_eq!(threaded, serial);
+        assert_eq!(costs(&threaded), costs(&serial));
+    }
+    assert_eq!(map.set_threads(Some(0)), Ok(()));
+    assert_eq!(map.threads(), None);
+}
+
+#[test]
+fn test_vertex_and_edge_count() {
+    let mut map = ACOMap::try_new(3, 3, 0.1).unwrap();
+    assert_eq!(map.vertex_count(), 9);
+    assert_eq!(map.edge_count(), 20);
+
+    map.set_connectivity(Connectivity::Four);
+    assert_eq!(map.edge_count(), 12);
+
+    map.set_connectivity(Connectivity::Eight);
+    map.set_obstacle(VerticeLoc::new(1, 1)).unwrap();
+    assert_eq!(map.vertex_count(), 9);
+    assert_eq!(map.edge_count(), 12);
+}
+
+#[test]
+fn test_visible_vertices_culling() {
+    let map = ACOMap::try_new(10, 10, 0.1).unwrap();
+    let window_size = (100, 100);
+
+    let all = map.visible_vertices(window_size, Viewport::from_window(window_size));
+    assert_eq!(all.len(), 100);
+    assert_eq!(all[0], VerticeLoc::new(0, 0));
+    assert_eq!(all[1], VerticeLoc::new(1, 0));
+    assert_eq!(all[10], VerticeLoc::new(0, 1));
+
+    let off_screen = Viewport {min: (500.0, 500.0), max: (600.0, 600.0)};
+    assert!(map.visible_vertices(window_size, off_screen).is_empty());
+    let left_of_screen = Viewport {min: (-200.0, 0.0), max: (-100.0, 100.0)};
+    assert!(map.visible_vertices(window_size, left_of_screen).is_empty());
+
+    let top_left = Viewport {min: (0.0, 0.0), max: (50.0, 50.0)};
+    let visible = map.visible_vertices(window_size, top_left);
+    assert!(!visible.is_empty() && visible.len() < all.len());
+    visible.iter().for_each(|vertice| {
+        let (x, y) = map.vertice_coordinates(window_size, *vertice);
+        assert!(x <= 50.0 + map.vertice_radius(window_size) && y <= 50.0 + map.vertice_radius(window_size));
+    });
+}
+
+#[test]
+fn test_heavy_evaporation_stays_finite() {
+    let mut map = ACOMap::try_new(4, 4, 0.999).unwrap();
+    map.set_iterations(100);
+    map.find_path(VerticeLoc::new(0, 0), VerticeLoc::new(3, 3));
+    (0..1000).for_each(|_| map.evaporate());
+    map.pheromone_graph.for_each(|_, _, pheromone| assert!(pheromone.is_finite() && pheromone >= MIN_PHEROMONE));
+}
+
+#[test]
+fn test_anti_pheromone_repels_recent_branch() {
+    // Start in the middle of a corridor with a branch to either side
+    let mut map = ACOMap::try_new(3, 1, 0.1).unwrap();
+    map.set_seed(Some(6));
+    let start = VerticeLoc::new(1, 0);
+    let left = VerticeLoc::new(0, 0);
+    let right = VerticeLoc::new(2, 0);
+    let lefts = |map: &ACOMap| {
+        let mut rng = stream_rng(map.seed(), 0);
+        (0..1000)
+            .filter(|_| map.get_next_vertice_for(&AntProfile::default(), start, &vec![start], &mut rng) == Some(left))
+            .count()
+    };
+
+    map.record_move(start, left);
+    assert!((400..600).contains(&lefts(&map)));
+
+    map.set_anti_pheromone_weight(0.9);
+    map.record_move(start, left);
+    assert!(lefts(&map) < 200);
+
+    (0..20).for_each(|_| map.decay_recent_usage());
+    assert!((400..600).contains(&lefts(&map)));
+    assert!([Ok(Some(left)), Ok(Some(right))].contains(&map.get_next_vertice(start)));
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_from_image_dark_pixels_become_obstacles() {
+    let dark = [(1, 0), (2, 2), (0, 3)];
+    let image = image::GrayImage::from_fn(3, 4, |x, y| {
+        if dark.contains(&(x, y)) { image::Luma([20]) } else { image::Luma([230]) }
+    });
+    // Parallel runs of the suite must not share the file
+    let path = std::env::temp_dir().join(format!("aco_pathfind_from_image_{}.png", std::process::id()));
+    image.save(&path).unwrap();
+
+    let map = ACOMap::from_image(&path, 128).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(map.vertex_count(), 12);
+    for y in 0..4 {
+        for x in 0..3 {
+            assert_eq!(map.is_obstacle(VerticeLoc::new(x, y)), dark.contains(&(x as u32, y as u32)));
+        }
+    }
+    assert!(matches!(ACOMap::from_image("does/not/exist.png", 128), Err(AcoError::Image(_))));
+
+    let path = std::env::temp_dir().join(format!("aco_pathfind_from_image_large_{}.png", std::process::id()));
+    image::GrayImage::from_fn(65, 64, |_, _| image::Luma([230])).save(&path).unwrap();
+    let result = ACOMap::from_image(&path, 128);
+    std::fs::remove_file(&path).ok();
+    assert_eq!(result.err(), Some(AcoError::ImageTooLarge(65, 64)));
+}
+
+#[test]
+fn test_cost_history_non_increasing() {
+    let mut map = ACOMap::try_new(6, 6, 0.1).unwrap();
+    map.set_iterations(25);
+    map.find_path(VerticeLoc::new(0, 0), VerticeLoc::new(5, 4));
+    assert!(map.cost_history().is_empty());
+
+    map.set_record_history(true);
+    map.find_path(VerticeLoc::new(0, 0), VerticeLoc::new(5, 4));
+    assert_eq!(map.cost_history().len(), 25);
+    assert!(map.cost_history().windows(2).all(|costs| costs[1] <= costs[0]));
+
+    map.find_path(VerticeLoc::new(0, 0), VerticeLoc::new(5, 4));
+    assert_eq!(map.cost_history().len(), 25);
+}
+
+#[test]
+fn test_fuel_budget() {
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    map.set_seed(Some(4));
+    map.set_iterations(20);
+    let start = VerticeLoc::new(0, 0);
+    let goal = VerticeLoc::new(4, 4);
+    let shortest = map.astar(start, goal).unwrap().cost;
+
+    map.set_fuel(Some(shortest - 0.1));
+    assert_eq!(map.try_find_path(start, goal), Err(AcoError::NoPathFound));
+
+    map.set_fuel(Some(shortest * 3.0));
+    let result = map.find_path(start, goal).unwrap();
+    assert!(result.cost <= shortest * 3.0);
+}
+
+#[test]
+fn test_neighbours_toward_east() {
+    let mut map = ACOMap::try_new(3, 3, 0.1).unwrap();
+    let center = VerticeLoc::new(1, 1);
+    let mut east = map.neighbours_toward(center, (1, 0));
+    east.sort();
+    assert_eq!(east, vec![VerticeLoc::new(2, 0), VerticeLoc::new(2, 1), VerticeLoc::new(2, 2)]);
+
+    map.set_obstacle(VerticeLoc::new(2, 0)).unwrap();
+    assert_eq!(map.neighbours_toward(center, (1, 0)).len(), 2);
+    let mut north_east = map.neighbours_toward(center, (1, -1));
+    north_east.sort();
+    assert_eq!(north_east, vec![VerticeLoc::new(1, 0), VerticeLoc::new(2, 1)]);
+    assert!(map.neighbours_toward(VerticeLoc::new(2, 1), (1, 0)).is_empty());
+}
+
+#[test]
+fn test_simulate_open_grid() {
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    map.deposit_pheromone(&[VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)], 5.0).unwrap();
+    let stats = map.simulate(VerticeLoc::new(0, 0), VerticeLoc::new(4, 4), 4, 10);
+    assert_eq!(stats.success_rate, 1.0);
+    assert!(stats.mean_cost.unwrap() >= 4.0 * DIAGONAL_COST - 1e-3);
+    assert!(stats.std_cost.unwrap() >= 0.0);
+    assert!((1.0..=10.0).contains(&stats.mean_iterations.unwrap()));
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)), 6.0);
+
+    let mut walled = ACOMap::try_new(3, 3, 0.1).unwrap();
+    (0..3).for_each(|y| walled.set_obstacle(VerticeLoc::new(1, y)).unwrap());
+    let stats = walled.simulate(VerticeLoc::new(0, 0), VerticeLoc::new(2, 2), 2, 3);
+    assert_eq!(stats, SimStats {mean_cost: None, std_cost: None, success_rate: 0.0, mean_iterations: None});
+    assert_eq!(walled.simulate(VerticeLoc::new(0, 0), VerticeLoc::new(2, 2), 0, 3).success_rate, 0.0);
+}
+
+#[test]
+fn test_set_evaporation_rate() {
+    let mut map = ACOMap::try_new(3, 3, 0.1).unwrap();
+    assert_eq!(map.set_evaporation_rate(0.5), Ok(()));
+    assert_eq!(map.evaporation_rate(), 0.5);
+    map.evaporate();
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)), 0.5);
+
+    assert_eq!(map.set_evaporation_rate(1.5), Err(AcoError::InvalidEvaporationRate(1.5)));
+    assert_eq!(map.set_evaporation_rate(-0.1), Err(AcoError::InvalidEvaporationRate(-0.1)));
+    assert_eq!(map.evaporation_rate(), 0.5);
+}
+
+#[test]
+fn test_edge_betweenness_ranks_corridor_first() {
+    // Two rooms joined by a single file corridor through (3, 1), (4, 1) and (5, 1)
+    let mut map = ACOMap::try_new(9, 3, 0.1).unwrap();
+    for x in 3..6 {
+        map.set_obstacle(VerticeLoc::new(x, 0)).unwrap();
+        map.set_obstacle(VerticeLoc::new(x, 2)).unwrap();
+    }
+    map.set_iterations(20);
+    map.find_path(VerticeLoc::new(0, 0), VerticeLoc::new(8, 2)).unwrap();
+
+    let corridor = [(VerticeLoc::new(3, 1), VerticeLoc::new(4, 1)), (VerticeLoc::new(4, 1), VerticeLoc::new(5, 1))];
+    let betweenness = map.edge_betweenness();
+    assert!((betweenness.iter().map(|(_, share)| share).sum::<f32>() - 1.0).abs() < 1e-3);
+    let weakest_corridor = betweenness.iter()
+        .filter(|(edge, _)| corridor.contains(edge))
+        .map(|(_, share)| *share)
+        .fold(f32::MAX, f32::min);
+    let strongest_elsewhere = betweenness.iter()
+        .filter(|(edge, _)| !corridor.contains(edge))
+        .map(|(_, share)| *share)
+        .fold(0.0, f32::max);
+    assert!(weakest_corridor >= strongest_elsewhere);
+}
+
+#[test]
+fn test_find_path_islands_migrates() {
+    struct Recorder {
+        iterations: Vec<usize>,
+        migrations: Vec<usize>,
+        best_costs: Vec<f32>
+    }
+
+    impl SolverObserver for Recorder {
+        fn on_iteration_complete(&mut self, iteration: usize, _best: Option<&PathResult>) {
+            self.iterations.push(iteration);
+        }
+
+        fn on_new_best(&mut self, _iteration: usize, best: &PathResult) {
+            self.best_costs.push(best.cost);
+        }
+
+        fn on_migration(&mut self, iteration: usize) {
+            self.migrations.push(iteration);
+        }
+    }
+
+    let mut map = ACOMap::try_new(6, 6, 0.1).unwrap();
+    map.set_iterations(12);
+    let mut recorder = Recorder {iterations: Vec::new(), migrations: Vec::new(), best_costs: Vec::new()};
+    let best = map.find_path_islands_observed(VerticeLoc::new(0, 0), VerticeLoc::new(5, 5), 2, 4, &mut recorder)
+        .unwrap();
+
+    assert_eq!(recorder.iterations, (0..12).collect::<Vec<_>>());
+    assert_eq!(recorder.migrations, vec![3, 7, 11]);
+    assert!(recorder.best_costs.windows(2).all(|costs| costs[1] < costs[0]));
+    assert_eq!(recorder.best_costs.last(), Some(&best.cost));
+    assert!(best.path.is_valid(&map));
+    assert_eq!(map.find_path_islands(VerticeLoc::new(0, 0), VerticeLoc::new(5, 5), 0, 4), None);
+}
+
+#[test]
+fn test_online_deposit_while_walking() {
+    let mut map = ACOMap::try_new(4, 1, 0.1).unwrap();
+    map.set_online_deposit(Some(OnlineDeposit {per_step: 0.25}));
+    let mut ants = [AntState::with_goal(VerticeLoc::new(0, 0), VerticeLoc::new(3, 0))];
+
+    assert!(map.step_all_ants(&mut ants));
+    assert!(map.step_all_ants(&mut ants));
+    assert_eq!(ants[0].current, VerticeLoc::new(2, 0));
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)), 1.25);
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(1, 0), VerticeLoc::new(2, 0)), 1.25);
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(2, 0), VerticeLoc::new(3, 0)), 1.0);
+
+    map.set_online_deposit(None);
+    assert!(!map.step_all_ants(&mut ants));
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(2, 0), VerticeLoc::new(3, 0)), 1.0 + 1.0 / 3.0);
+}
+
+#[test]
+fn test_online_deposit_during_construction() {
+    let mut map = ACOMap::try_new(4, 1, 0.1).unwrap();
+    map.set_online_deposit(Some(OnlineDeposit {per_step: 0.5}));
+    let (start, goal) = (VerticeLoc::new(0, 0), VerticeLoc::new(3, 0));
+    let results = map.construct_paths_online(&[start, start], goal, &mut StdRng::seed_from_u64(1));
+    assert_eq!(results.len(), 2);
+    (0..3).for_each(|x| assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(x, 0), VerticeLoc::new(x + 1, 0)), 2.0));
+
+    // The colony lays the online deposit while building paths and not a second time after
+    let mut map = ACOMap::try_new(4, 1, 0.0).unwrap();
+    map.set_ant_count(2);
+    map.set_online_deposit(Some(OnlineDeposit {per_step: 0.5}));
+    AntColony::new(&mut map, start, goal).step();
+    assert!((map.pheromone_graph.get_edg_value(start, VerticeLoc::new(1, 0)) - (2.0 + 2.0 / 3.0)).abs() < 1e-5);
+}
+
+#[test]
+fn test_zero_window_size_is_a_no_op() {
+    let map = ACOMap::try_new(4, 4, 0.1).unwrap();
+    for window_size in [(0, 100), (100, 0), (0, 0)] {
+        assert!(map.visible_vertices(window_size, Viewport::from_window(window_size)).is_empty());
+        assert!(map.grid_lines(window_size).is_empty());
+        assert_eq!(map.get_vertice_coordinates(window_size, VerticeLoc::new(1, 1)),
+            Err(AcoError::InvalidWindowSize(window_size.0, window_size.1)));
+    }
+    assert!(map.get_vertice_coordinates((100, 100), VerticeLoc::new(1, 1)).is_ok());
+}
+
+#[test]
+fn test_neighbour_pheromone_sum() {
+    let mut map = ACOMap::try_new(4, 4, 0.1).unwrap();
+    for vertice in [VerticeLoc::new(0, 0), VerticeLoc::new(1, 1), VerticeLoc::new(3, 2)] {
+        assert_eq!(map.neighbour_pheromone_sum(vertice), map.neighbour_count(vertice) as f32 * 1.0);
+    }
+
+    map.deposit_pheromone(&[VerticeLoc::new(1, 1), VerticeLoc::new(2, 1)], 2.0).unwrap();
+    map.set_obstacle(VerticeLoc::new(0, 0)).unwrap();
+    assert_eq!(map.neighbour_pheromone_sum(VerticeLoc::new(1, 1)), 7.0 + 2.0);
+}
+
+#[test]
+fn test_cost_models() {
+    let mut map = ACOMap::try_new(4, 4, 0.1).unwrap();
+    let origin = VerticeLoc::new(0, 0);
+    assert_eq!(map.cost(origin, VerticeLoc::new(1, 1)), DIAGONAL_COST);
+
+    map.set_cost_model(CostModel::Uniform);
+    assert_eq!(map.cost(origin, VerticeLoc::new(1, 1)), 1.0);
+    assert_eq!(map.cost(origin, VerticeLoc::new(1, 0)), 1.0);
+    assert_eq!(map.octile_distance(origin, VerticeLoc::new(3, 2)), 3.0);
+    assert_eq!(map.astar(origin, VerticeLoc::new(3, 3)).unwrap().cost, 3.0);
+
+    map.set_cost_model(CostModel::Manhattan);
+    assert_eq!(map.cost(origin, VerticeLoc::new(1, 1)), 2.0);
+    assert_eq!(map.octile_distance(origin, VerticeLoc::new(3, 2)), 5.0);
+}
+
+#[test]
+fn test_seeded_islands_are_reproducible() {
+    for order in [NeighbourOrder::Fixed, NeighbourOrder::Shuffled, NeighbourOrder::Rotating] {
+        let run = |threads: Option<usize>| {
+            let mut map = ACOMap::try_new(7, 7, 0.1).unwrap();
+            map.set_seed(Some(42));
+            map.set_threads(threads).unwrap();
+            map.set_iterations(8);
+            map.set_neighbour_order(order);
+            map.find_path_islands(VerticeLoc::new(0, 0), VerticeLoc::new(6, 5), 3, 3).unwrap()
+        };
+        let first = run(None);
+        assert_eq!(run(None), first);
+        assert_eq!(run(Some(2)), first);
+        assert_eq!(run(Some(4)), first);
+    }
+}
+
+#[test]
+fn test_shortest_hop_path() {
+    let mut map = ACOMap::try_new(6, 6, 0.1).unwrap();
+    let start = VerticeLoc::new(0, 0);
+    let goal = VerticeLoc::new(5, 3);
+
+    let hops = map.shortest_hop_path(start, goal).unwrap();
+    assert_eq!(hops.len() - 1, 5);
+    assert!(Path::from(hops.clone()).is_valid(&map));
+    assert!(hops.len() <= map.astar(start, goal).unwrap().path.len());
+    (0..20).filter_map(|_| map.construct_path(start, goal)).for_each(|result| assert!(hops.len() <= result.path.len()));
+
+    assert_eq!(map.shortest_hop_path(start, start), Some(vec![start]));
+    (0..6).for_each(|y| map.set_obstacle(VerticeLoc::new(2, y)).unwrap());
+    assert_eq!(map.shortest_hop_path(start, goal), None);
+}
+
+#[test]
+fn test_dominant_source() {
+    // Each source has its own single file lane, both joining at the goal
+    let mut map = ACOMap::try_new(5, 3, 0.1).unwrap();
+    (0..3).for_each(|x| map.set_obstacle(VerticeLoc::new(x, 1)).unwrap());
+    map.set_iterations(10);
+    let sources = [(VerticeLoc::new(0, 0), 1.0), (VerticeLoc::new(0, 2), 1.0)];
+    map.find_path_from(&sources, VerticeLoc::new(4, 1)).unwrap();
+
+    assert_eq!(map.dominant_source(VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)), Some(0));
+    assert_eq!(map.dominant_source(VerticeLoc::new(1, 2), VerticeLoc::new(0, 2)), Some(1));
+
+    let mut map = ACOMap::try_new(3, 3, 0.1).unwrap();
+    let edge = [VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)];
+    assert_eq!(map.dominant_source(edge[0], edge[1]), None);
+    map.deposit_source(0, &edge, 1.0).unwrap();
+    map.deposit_source(2, &edge, 3.0).unwrap();
+    assert_eq!(map.dominant_source(edge[1], edge[0]), Some(2));
+}
+
+#[test]
+fn test_prune_dead_ends() {
+    // A corridor along the top row from start to goal with a dead end branch hanging off
+    // (2, 0) down to (2, 3), everything else walled off
+    let mut map = ACOMap::try_new(5, 4, 0.1).unwrap();
+    map.set_connectivity(Connectivity::Four);
+    for y in 1..4 {
+        for x in [0, 1, 3, 4] {
+            map.set_obstacle(VerticeLoc::new(x, y)).unwrap();
+        }
+    }
+    let start = VerticeLoc::new(0, 0);
+    let goal = VerticeLoc::new(4, 0);
+    map.set_goal(Some(goal)).unwrap();
+    map.set_start(Some(start)).unwrap();
+
+    assert_eq!(map.prune_dead_ends(), 3);
+    (1..4).for_each(|y| assert!(map.is_obstacle(VerticeLoc::new(2, y))));
+    assert!(!map.is_obstacle(start) && !map.is_obstacle(goal));
+    assert_eq!(map.shortest_hop_path(start, goal).map(|path| path.len()), Some(5));
+    assert_eq!(map.prune_dead_ends(), 0);
+
+    // A pruned vertice the user blocks for real stays blocked
+    map.set_obstacle(VerticeLoc::new(2, 3)).unwrap();
+    map.restore_pruned();
+    (1..3).for_each(|y| assert!(!map.is_obstacle(VerticeLoc::new(2, y))));
+    assert!(map.is_obstacle(VerticeLoc::new(2, 3)));
+}
+
+#[test]
+fn test_vertice_labels() {
+    let map = ACOMap::try_new(2, 2, 0.1).unwrap();
+    let labels: Vec<String> = map.vertice_labels((200, 200)).into_iter().map(|(_, label, _)| label).collect();
+    assert_eq!(labels, vec!["(0, 0)", "(1, 0)", "(0, 1)", "(1, 1)"]);
+
+    let large = ACOMap::try_new(30, 30, 0.1).unwrap();
+    assert!(large.vertice_labels((600, 600)).is_empty());
+}
+
+#[test]
+fn test_exploitation_breaks_ties_randomly() {
+    let mut map = ACOMap::try_new(3, 1, 0.5).unwrap();
+    let profile = AntProfile {alpha: 1.0, beta: 1.0, q0: 1.0};
+    let (current, left) = (VerticeLoc::new(1, 0), VerticeLoc::new(0, 0));
+    let mut rng = StdRng::seed_from_u64(3);
+
+    const TRIALS: usize = 2000;
+    let lefts = (0..TRIALS)
+        .filter(|_| map.get_next_vertice_for(&profile, current, &Vec::new(), &mut rng) == Some(left))
+        .count();
+    let frq_left = lefts as f32 / TRIALS as f32;
+    assert!((frq_left - 0.5).abs() < 0.05, "left picked {} of the time", frq_left);
+
+    map.set_random_tie_break(false);
+    let first = map.get_next_vertice_for(&profile, current, &Vec::new(), &mut rng);
+    assert!((0..100).all(|_| map.get_next_vertice_for(&profile, current, &Vec::new(), &mut rng) == first));
+}
+
+#[test]
+fn test_with_pheromone_matrix() {
+    let (v00, v10, v11) = (VerticeLoc::new(0, 0), VerticeLoc::new(1, 0), VerticeLoc::new(1, 1));
+    let mut edges = HashMap::new();
+    edges.insert((v00, v10), 0.25);
+    edges.insert((v11, v10), 2.0);
+    edges.insert((v10, v11), 2.0);
+
+    let map = ACOMap::with_pheromone_matrix(3, 3, 0.1, &edges).unwrap();
+    assert_eq!(map.pheromone_graph.get_edg_value(v00, v10), 0.25);
+    assert_eq!(map.pheromone_graph.get_edg_value(v10, v00), 0.25);
+    assert_eq!(map.pheromone_graph.get_edg_value(v10, v11), 2.0);
+    assert_eq!(map.pheromone_graph.get_edg_value(v00, v11), ACOMap::try_new(3, 3, 0.1).unwrap().pheromone_graph.get_edg_value(v00, v11));
+
+    edges.insert((v11, v10), 3.0);
+    assert!(matches!(ACOMap::with_pheromone_matrix(3, 3, 0.1, &edges), Err(AcoError::InvalidEdge(_, _))));
+
+    let mut edges = HashMap::new();
+    edges.insert((v00, VerticeLoc::new(2, 0)), 1.0);
+    assert_eq!(ACOMap::with_pheromone_matrix(3, 3, 0.1, &edges).err(), Some(AcoError::InvalidEdge(v00, VerticeLoc::new(2, 0))));
+    edges.clear();
+    edges.insert((v00, VerticeLoc::new(3, 0)), 1.0);
+    assert_eq!(ACOMap::with_pheromone_matrix(3, 3, 0.1, &edges).err(), Some(AcoError::OutOfBounds));
+}
+
+#[test]
+fn test_validate_field_reports_non_finite_pheromone() {
+    let mut map = ACOMap::try_new(3, 3, 0.1).unwrap();
+    assert_eq!(map.validate_field(), Ok(()));
+
+    let (v0, v1) = (VerticeLoc::new(1, 1), VerticeLoc::new(2, 2));
+    map.pheromone_graph.set_edg_value(v1, v0, f32::NAN);
+    assert_eq!(map.validate_field(), Err(AcoError::NonFinitePheromone(v0, v1)));
+
+    map.pheromone_graph.set_edg_value(v0, v1, f32::INFINITY);
+    assert_eq!(map.validate_field(), Err(AcoError::NonFinitePheromone(v0, v1)));
+}
+
+#[test]
+fn test_search_bounds_keep_ants_inside() {
+    let mut map = ACOMap::try_new(20, 20, 0.1).unwrap();
+    map.set_iterations(10);
+    let (min, max) = (VerticeLoc::new(5, 5), VerticeLoc::new(10, 10));
+    let inside = |v: &VerticeLoc| (5..=10).contains(&v.x()) && (5..=10).contains(&v.y());
+    map.set_search_bounds(Some((max, min)));
+    assert_eq!(map.search_bounds(), Some((min, max)));
+    assert!(map.get_neighbours(max).iter().all(inside));
+
+    let mut rng = StdRng::seed_from_u64(5);
+    let start = VerticeLoc::new(6, 6);
+    let goal = VerticeLoc::new(9, 8);
+    map.set_goal(Some(goal)).unwrap();
+    for _ in 0..50 {
+        if let Some(walk) = map.construct_path_with_profile(start, goal, &AntProfile::default(), &mut rng) {
+            assert!(walk.path.iter().all(inside));
+        }
+    }
+    map.set_goal(None).unwrap();
+
+    let result = map.find_path(start, goal).unwrap();
+    assert!(result.path.iter().all(inside));
+
+    // Without explicit bounds the margin spans a box around start and goal for the run only
+    map.set_search_bounds(None);
+    map.set_search_margin(Some(1));
+    let result = map.find_path(start, goal).unwrap();
+    assert!(result.path.iter().all(|v| (5..=10).contains(&v.x()) && (5..=9).contains(&v.y())));
+    assert_eq!(map.search_bounds(), None);
+}
+
+#[test]
+fn test_search_bounds_are_not_obstacles() {
+    let mut map = ACOMap::try_new(6, 6, 0.1).unwrap();
+    map.set_search_bounds(Some((VerticeLoc::new(0, 0), VerticeLoc::new(2, 2))));
+    let outside = VerticeLoc::new(4, 4);
+    assert!(!map.is_obstacle(outside));
+    assert!(!map.is_traversable(outside));
+    assert!(map.is_traversable(VerticeLoc::new(2, 2)));
+    assert!(!map.is_traversable(VerticeLoc::new(6, 0)));
+    assert_eq!(map.obstacle_count(), 0);
+    assert_eq!(map.cost_colors(|vertice| vertice.x() as f32).len(), 36);
+    assert_eq!(map.edges().len(), ACOMap::try_new(6, 6, 0.1).unwrap().edges().len());
+
+    // The border of the bounds is no dead end of the map
+    map.set_search_bounds(Some((VerticeLoc::new(0, 0), VerticeLoc::new(5, 0))));
+    assert_eq!(map.prune_dead_ends(), 0);
+}
+
+#[test]
+fn test_search_margin_applies_to_every_search() {
+    let (start, goal) = (VerticeLoc::new(2, 2), VerticeLoc::new(5, 4));
+    let inside = |path: &Path| path.iter().all(|v| (1..=6).contains(&v.x()) && (1..=5).contains(&v.y()));
+    let mut map = ACOMap::try_new(12, 12, 0.1).unwrap();
+    map.set_iterations(5);
+    map.set_search_margin(Some(1));
+
+    assert!(map.find_path_anytime(start, goal).all(|result| inside(&result.path)));
+    assert!(inside(&map.solve_with_timeout_and_callback(start, goal, std::time::Duration::from_millis(20), |_| ()).unwrap().path));
+    assert!(inside(&map.find_path_islands(start, goal, 2, 2).unwrap().path));
+    assert_eq!(map.simulate(start, goal, 2, 5).success_rate, 1.0);
+    assert_eq!(map.search_bounds(), None);
+}
+
+#[test]
+fn test_search_margin_falls_back_to_whole_map() {
+    // A wall cuts through the box around start and goal, the only way round lies outside it
+    let mut map = ACOMap::try_new(10, 10, 0.1).unwrap();
+    (2..=8).for_each(|y| map.set_obstacle(VerticeLoc::new(5, y)).unwrap());
+    map.set_search_margin(Some(1));
+    let (start, goal) = (VerticeLoc::new(2, 5), VerticeLoc::new(7, 5));
+    let result = map.find_path(start, goal).unwrap();
+    assert!(result.path.iter().any(|v| v.y() < 4 || v.y() > 6));
+    assert_eq!(map.search_bounds(), None);
+}
+
+#[test]
+fn test_difficulty_estimate() {
+    let (start, goal) = (VerticeLoc::new(0, 0), VerticeLoc::new(8, 0));
+    let mut map = ACOMap::try_new(9, 9, 0.1).unwrap();
+    assert_eq!(map.difficulty_estimate(start, goal), 1.0);
+
+    // Walls from alternating sides turn the way to the goal into a winding corridor
+    for x in [2, 6] {
+        (0..8).for_each(|y| map.set_obstacle(VerticeLoc::new(x, y)).unwrap());
+    }
+    (1..9).for_each(|y| map.set_obstacle(VerticeLoc::new(4, y)).unwrap());
+    let maze = map.difficulty_estimate(start, goal);
+    assert!(maze > 2.0, "maze difficulty {}", maze);
+
+    (0..9).for_each(|y| map.set_obstacle(VerticeLoc::new(4, y)).unwrap());
+    assert_eq!(map.difficulty_estimate(start, goal), f32::INFINITY);
+}
+
+#[test]
+fn test_line_of_sight() {
+    let mut map = ACOMap::try_new(6, 6, 0.5).unwrap();
+    let (v00, v33) = (VerticeLoc::new(0, 0), VerticeLoc::new(3, 3));
+    assert!(map.line_of_sight(v00, v33));
+    assert!(map.line_of_sight(v00, VerticeLoc::new(5, 2)));
+    assert!(map.line_of_sight(v00, v00));
+    assert!(!map.line_of_sight(v00, VerticeLoc::new(6, 0)));
+
+    // Grazing the corner of a single obstacle beside the diagonal
+    map.set_obstacle(VerticeLoc::new(1, 0)).unwrap();
+    assert!(map.line_of_sight(v00, v33));
+    // Squeezing between two obstacles that touch diagonally
+    map.set_obstacle(VerticeLoc::new(0, 1)).unwrap();
+    assert!(!map.line_of_sight(v00, v33));
+    assert!(!map.line_of_sight(v00, VerticeLoc::new(1, 0)));
+
+    // Straight through a wall
+    (0..6).for_each(|y| map.set_obstacle(VerticeLoc::new(4, y)).unwrap());
+    assert!(!map.line_of_sight(VerticeLoc::new(2, 3), VerticeLoc::new(5, 3)));
+    assert!(map.line_of_sight(VerticeLoc::new(2, 3), VerticeLoc::new(3, 3)));
+}
+
+#[test]
+fn test_total_pheromone() {
+    let mut map = ACOMap::try_new(3, 3, 0.25).unwrap();
+    // Every edge starts at 1.0 and is counted once, not once per direction
+    assert_eq!(map.total_pheromone(), map.edge_count() as f32);
+
+    map.deposit_pheromone(&[VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)], 2.0).unwrap();
+    let before = map.total_pheromone();
+    assert!((before - (map.edge_count() as f32 + 2.0)).abs() < 1e-4);
+
+    map.evaporate();
+    assert!((map.total_pheromone() - before * 0.75).abs() < 1e-4);
+}
+
+#[test]
+fn test_fill_neighbours_reuses_buffer() {
+    let mut map = ACOMap::try_new(4, 4, 0.5).unwrap();
+    map.set_obstacle(VerticeLoc::new(1, 1)).unwrap();
+    map.forbid_edge(VerticeLoc::new(2, 2), VerticeLoc::new(3, 3)).unwrap();
+
+    let mut buffer = Vec::new();
+    for _ in 0..2 {
+        for y in 0..4 {
+            for x in 0..4 {
+                let vertice = VerticeLoc::new(x, y);
+                map.fill_neighbours(vertice, &mut buffer);
+                assert_eq!(buffer, map.get_neighbours(vertice));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_init_jitter() {
+    let map = ACOMapBuilder::new(5, 5).init_jitter(0.1).build().unwrap();
+    let pheromones: Vec<f32> = map.edges().into_iter().map(|(v0, v1)| map.pheromone_graph.get_edg_value(v0, v1)).collect();
+    assert!(pheromones.iter().any(|pheromone| *pheromone != pheromones[0]));
+    assert!(pheromones.iter().all(|pheromone| (0.9..=1.1).contains(pheromone)));
+
+    let field = |map: &ACOMap| -> Vec<f32> {
+        map.edges().into_iter().map(|(v0, v1)| map.pheromone_graph.get_edg_value(v0, v1)).collect()
+    };
+    let first = ACOMapBuilder::new(5, 5).init_jitter(0.1).seed(7).build().unwrap();
+    let second = ACOMapBuilder::new(5, 5).init_jitter(0.1).seed(7).build().unwrap();
+    assert_eq!(first.seed(), Some(7));
+    assert_eq!(field(&first), field(&second));
+
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    map.set_seed(Some(3));
+    map.set_init_jitter(0.2);
+    map.reset_pheromones();
+    let first: Vec<f32> = map.edges().into_iter().map(|(v0, v1)| map.pheromone_graph.get_edg_value(v0, v1)).collect();
+    map.reset_pheromones();
+    let second: Vec<f32> = map.edges().into_iter().map(|(v0, v1)| map.pheromone_graph.get_edg_value(v0, v1)).collect();
+    assert_eq!(first, second);
+
+    map.set_init_jitter(0.0);
+    map.reset_pheromones();
+    assert_eq!(map.total_pheromone(), map.edge_count() as f32);
+}
+
+#[test]
+fn test_reset_edges_of() {
+    let mut map = ACOMapBuilder::new(4, 3).init_jitter(0.2).seed(9).build().unwrap();
+    let center = VerticeLoc::new(1, 1);
+    map.deposit_pheromone(&[center, VerticeLoc::new(2, 1)], 5.0).unwrap();
+    map.reset_edges_of(center).unwrap();
+    let around: Vec<f32> = map.get_neighbours(center).into_iter()
+        .map(|neighbour| map.pheromone_graph.get_edg_value(center, neighbour))
+        .collect();
+    assert!(around.iter().all(|pheromone| (0.8..=1.2).contains(pheromone)));
+    assert!(around.iter().any(|pheromone| *pheromone != around[0]));
+
+    // x = 4 would alias onto (0, 1), the last row has no row to alias onto
+    let before = map.clone();
+    assert_eq!(map.reset_edges_of(VerticeLoc::new(4, 0)), Err(AcoError::OutOfBounds));
+    assert_eq!(map.reset_edges_of(VerticeLoc::new(0, 3)), Err(AcoError::OutOfBounds));
+    assert!(map.pheromone_graph == before.pheromone_graph);
+}
+
+#[test]
+fn test_transition_entropy() {
+    let mut map = ACOMap::try_new(3, 3, 0.1).unwrap();
+    map.set_cost_model(CostModel::Uniform);
+    let center = VerticeLoc::new(1, 1);
+    assert!((map.transition_entropy(center) - (8.0f32).ln()).abs() < 1e-4);
+
+    map.deposit_pheromone(&[center, VerticeLoc::new(2, 1)], 1e6).unwrap();
+    assert!(map.transition_entropy(center) < 1e-3);
+
+    let single = ACOMap::try_new(1, 1, 0.1).unwrap();
+    assert_eq!(single.transition_entropy(VerticeLoc::new(0, 0)), 0.0);
+}
+
+#[test]
+fn test_step_ant_gives_up_after_max_backtracks() {
+    let map = ACOMap::try_new(5, 1, 0.5).unwrap();
+    let mut ant = AntState::new(VerticeLoc::new(0, 0));
+    (1..5).for_each(|x| ant.advance(VerticeLoc::new(x, 0)));
+    // Every vertice the ant could step onto is already excluded
+    ant.exclusions = (0..5).map(|x| VerticeLoc::new(x, 0)).collect();
+
+    assert_eq!(map.step_ant(&mut ant, 2).unwrap(), StepOutcome::Stuck);
+    assert_eq!(ant.current, ant.start);
+    assert_eq!(ant.path, vec![ant.start]);
+}
+
+#[test]
+fn test_evaporation_map() {
+    let mut map = ACOMap::try_new(4, 4, 0.1).unwrap();
+    let mut rates = vec![0.1; map.vertex_count()];
+    // The right half of the map is volatile
+    (0..4).for_each(|y| (2..4).for_each(|x| rates[x + y * 4] = 0.5));
+    assert_eq!(map.set_evaporation_map(Some(vec![0.1; 3])), Err(AcoError::InvalidEvaporationMap(3)));
+    assert_eq!(map.set_evaporation_map(Some(vec![1.0; 16])), Err(AcoError::InvalidEvaporationRate(1.0)));
+    map.set_evaporation_map(Some(rates)).unwrap();
+
+    map.evaporate();
+    let pheromone = |v0: (usize, usize), v1: (usize, usize)| map.pheromone_graph.get_edg_value(v0.into(), v1.into());
+    assert!((pheromone((0, 0), (1, 0)) - 0.9).abs() < 1e-6);
+    assert!((pheromone((2, 0), (3, 0)) - 0.5).abs() < 1e-6);
+    // An edge crossing into the volatile region evaporates at its rate
+    assert!((pheromone((1, 1), (2, 1)) - 0.5).abs() < 1e-6);
+
+    map.set_evaporation_map(None).unwrap();
+    map.evaporate();
+    assert!((map.pheromone_graph.get_edg_value((2, 0).into(), (3, 0).into()) - 0.45).abs() < 1e-6);
+}
+
+#[test]
+fn test_find_path_anytime_improves() {
+    let mut map = ACOMap::try_new(10, 10, 0.1).unwrap();
+    map.set_seed(Some(11));
+    map.set_iterations(30);
+    let costs: Vec<f32> = map.find_path_anytime(VerticeLoc::new(0, 0), VerticeLoc::new(9, 6))
+        .map(|result| result.cost)
+        .collect();
+    assert!(!costs.is_empty());
+    assert!(costs.windows(2).all(|pair| pair[1] < pair[0]));
+
+    // The caller is free to stop after the first solution
+    assert_eq!(map.find_path_anytime(VerticeLoc::new(0, 0), VerticeLoc::new(9, 6)).take(1).count(), 1);
+    assert_eq!(map.find_path_anytime(VerticeLoc::new(0, 0), VerticeLoc::new(10, 6)).count(), 0);
+}
+
+#[test]
+fn test_neighbour_directions() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let center = VerticeLoc::new(1, 1);
+    let directions = map.neighbour_directions(center);
+    let mut offsets: Vec<(i32, i32)> = directions.iter().map(|(offset, _)| *offset).collect();
+    offsets.sort();
+    assert_eq!(offsets, vec![(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)]);
+    assert!(directions.iter().all(|((dx, dy), neighbour)| {
+        neighbour.x() as i32 == 1 + dx && neighbour.y() as i32 == 1 + dy
+    }));
+
+    map.set_connectivity(Connectivity::Four);
+    map.set_obstacle(VerticeLoc::new(2, 1)).unwrap();
+    let mut offsets: Vec<(i32, i32)> = map.neighbour_directions(center).into_iter().map(|(offset, _)| offset).collect();
+    offsets.sort();
+    assert_eq!(offsets, vec![(-1, 0), (0, -1), (0, 1)]);
+}
+
+#[test]
+fn test_deposit_amount() {
+    let mut map = ACOMap::try_new(4, 4, 0.1).unwrap();
+    assert_eq!(map.deposit_amount(), 1.0);
+    assert_eq!(map.set_deposit_amount(0.0), Err(AcoError::InvalidDepositAmount(0.0)));
+    assert!(map.set_deposit_amount(f32::NAN).is_err());
+    map.set_deposit_amount(5.0).unwrap();
+
+    let path = vec![VerticeLoc::new(0, 0), VerticeLoc::new(1, 0), VerticeLoc::new(2, 1)];
+    let cost = map.path_cost(&path);
+    map.deposit_pheromone_inverse_cost(&PathResult {path: path.clone().into(), cost}).unwrap();
+    path.windows(2).for_each(|edge| {
+        assert!((map.pheromone_graph.get_edg_value(edge[0], edge[1]) - (1.0 + 5.0 / cost)).abs() < 1e-6);
+    });
+}
+
+#[test]
+fn test_cost_colors_follow_cost_field() {
+    let mut map = ACOMap::try_new(4, 4, 0.5).unwrap();
+    map.set_obstacle(VerticeLoc::new(0, 3)).unwrap();
+    // Expensive swamp in the right half of the map
+    let colors = map.cost_colors(|vertice| if vertice.x() >= 2 { 10.0 } else { 1.0 });
+    assert_eq!(colors.len(), 15);
+    colors.iter().for_each(|(vertice, color)| {
+        if vertice.x() >= 2 {
+            assert_eq!((color.r(), color.g(), color.b()), (1.0, 0.0, 0.0));
+        } else {
+            assert_eq!((color.r(), color.g(), color.b()), (1.0, 1.0, 1.0));
+        }
+    });
+
+    assert!(map.cost_colors(|_| f32::INFINITY).is_empty());
+}
+
+#[test]
+fn test_neighbours_of_path() {
+    let mut map = ACOMap::try_new(5, 3, 0.5).unwrap();
+    let path: Vec<VerticeLoc> = (1..4).map(|x| VerticeLoc::new(x, 1)).collect();
+    let expected: HashSet<VerticeLoc> = (0..5)
+        .flat_map(|x| [VerticeLoc::new(x, 0), VerticeLoc::new(x, 2)])
+        .chain([VerticeLoc::new(0, 1), VerticeLoc::new(4, 1)])
+        .collect();
+    assert_eq!(map.neighbours_of_path(&path), expected);
+
+    map.set_obstacle(VerticeLoc::new(2, 0)).unwrap();
+    assert!(!map.neighbours_of_path(&path).contains(&VerticeLoc::new(2, 0)));
+    assert!(map.neighbours_of_path(&[]).is_empty());
+}
+
+#[test]
+fn test_improve_path_shortens_detour() {
+    let mut map = ACOMap::try_new(6, 6, 0.5).unwrap();
+    map.set_obstacle(VerticeLoc::new(2, 2)).unwrap();
+    map.set_obstacle(VerticeLoc::new(2, 3)).unwrap();
+    // Wanders around the far side of the map before reaching the goal
+    let path: Vec<VerticeLoc> = [(0, 2), (0, 3), (0, 4), (1, 5), (2, 5), (3, 5), (4, 4), (4, 3), (5, 2)]
+        .into_iter()
+        .map(VerticeLoc::from)
+        .collect();
+    let improved = map.improve_path(&path);
+    assert!(map.path_cost(&improved) < map.path_cost(&path));
+    assert_eq!(improved.first(), path.first());
+    assert_eq!(improved.last(), path.last());
+    improved.windows(2).for_each(|edge| assert_eq!(map.try_move(edge[0], edge[1]), Ok(())));
+
+    // Already as short as it gets
+    let straight: Vec<VerticeLoc> = (0..6).map(|x| VerticeLoc::new(x, 0)).collect();
+    assert_eq!(map.improve_path(&straight), straight);
+}
+
+#[test]
+fn test_export_csv() {
+    let mut map = ACOMap::try_new(2, 2, 0.5).unwrap();
+    map.deposit_pheromone(&[VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)], 0.5).unwrap();
+
+    let mut csv = Vec::new();
+    map.export_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("x0,y0,x1,y1,pheromone"));
+
+    let rows: Vec<Vec<f32>> = lines.map(|line| line.split(',').map(|value| value.parse().unwrap()).collect()).collect();
+    assert_eq!(rows.len(), map.edge_count());
+    assert_eq!(rows.len(), 6);
+    rows.iter().for_each(|row| {
+        let v0 = VerticeLoc::new(row[0] as usize, row[1] as usize);
+        let v1 = VerticeLoc::new(row[2] as usize, row[3] as usize);
+        assert_eq!(row[4], map.pheromone_graph.get_edg_value(v0, v1));
+    });
+    assert!(rows.iter().any(|row| row[4] == 1.5));
+}
+
+#[test]
+fn test_get_neighbours_where() {
+    let map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let center = VerticeLoc::new(1, 1);
+    // Nothing above the center row
+    let mut below = map.get_neighbours_where(center, |neighbour| neighbour.y() >= 1);
+    below.sort();
+    let mut expected: Vec<VerticeLoc> = [(0, 1), (2, 1), (0, 2), (1, 2), (2, 2)].into_iter().map(VerticeLoc::from).collect();
+    expected.sort();
+    assert_eq!(below, expected);
+
+    let exclusions = vec![VerticeLoc::new(0, 0)];
+    assert_eq!(map.get_neighbours_with_exclusions(center, &exclusions),
+        map.get_neighbours_where(center, |neighbour| neighbour != VerticeLoc::new(0, 0)));
+}
+
+#[test]
+fn test_clear_obstacles() {
+    let mut map = ACOMap::try_new(4, 4, 0.5).unwrap();
+    let center = VerticeLoc::new(1, 1);
+    [(0, 0), (1, 0), (2, 2), (1, 1)].into_iter().for_each(|obstacle| map.set_obstacle(obstacle.into()).unwrap());
+    assert_eq!(map.obstacle_count(), 4);
+    assert!(!map.get_neighbours(VerticeLoc::new(0, 1)).contains(&center));
+
+    map.clear_obstacles();
+    assert_eq!(map.obstacle_count(), 0);
+    assert!(map.get_neighbours(VerticeLoc::new(0, 1)).contains(&center));
+    assert!(map.changed_vertices().contains(&center));
+}
+
+#[test]
+fn test_neighbour_order() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    let center = VerticeLoc::new(1, 1);
+    let fixed = map.get_neighbours(center);
+    assert_eq!(map.get_neighbours(center), fixed);
+
+    let sorted = |mut neighbours: Vec<VerticeLoc>| { neighbours.sort(); neighbours };
+    for order in [NeighbourOrder::Shuffled, NeighbourOrder::Rotating] {
+        map.set_neighbour_order(order);
+        let mut rng = StdRng::seed_from_u64(3);
+        let orders: Vec<Vec<VerticeLoc>> = (0..10).map(|_| map.get_neighbours_ordered(center, &mut rng)).collect();
+        assert!(orders.iter().any(|neighbours| *neighbours != orders[0]), "{:?} never changed the order", order);
+        assert!(orders.iter().all(|neighbours| sorted(neighbours.clone()) == sorted(fixed.clone())));
+        // The same stream gives the same orders and plain lookups are left alone
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(orders.iter().all(|neighbours| *neighbours == map.get_neighbours_ordered(center, &mut rng)));
+        assert_eq!(map.get_neighbours(center), fixed);
+    }
+}
+
+#[test]
+fn test_path_result_deduplication() {
+    let route = vec![VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)];
+    let mut routes = HashSet::new();
+    routes.insert(PathResult {path: route.clone().into(), cost: std::f32::consts::SQRT_2});
+    routes.insert(PathResult {path: route.into(), cost: std::f32::consts::SQRT_2});
+    assert_eq!(routes.len(), 1);
+
+    routes.insert(PathResult {path: vec![VerticeLoc::new(0, 0), VerticeLoc::new(1, 0)].into(), cost: 1.0});
+    assert_eq!(routes.len(), 2);
+}
+
+#[test]
+fn test_path_pheromones() {
+    let mut map = ACOMap::try_new(5, 1, 0.5).unwrap();
+    let path: Vec<VerticeLoc> = (0..5).map(|x| VerticeLoc::new(x, 0)).collect();
+    map.deposit_pheromone(&path[1..3], 2.0).unwrap();
+
+    let pheromones = map.path_pheromones(&path).unwrap();
+    assert_eq!(pheromones.len(), 4);
+    assert!(pheromones[1] > pheromones[0] && pheromones[1] > pheromones[2]);
+    assert_eq!(pheromones[1], 3.0);
+
+    assert_eq!(map.path_pheromones(&[path[0], path[2]]), Err(AcoError::Unreachable(path[2])));
+    assert_eq!(map.path_pheromones(&[path[0], VerticeLoc::new(0, 1)]), Err(AcoError::OutOfBounds));
+    assert_eq!(map.path_pheromones(&path[..1]), Ok(Vec::new()));
+}
+
+#[test]
+fn test_pixel_to_vertice_inverts_coordinates() {
+    let map = ACOMap::try_new(10, 8, 0.5).unwrap();
+    let window_size = (800, 600);
+    for vertice in [(0, 0), (9, 7), (3, 5), (9, 0)].into_iter().map(VerticeLoc::from) {
+        let (x, y) = map.get_vertice_coordinates(window_size, vertice).unwrap();
+        assert_eq!(map.pixel_to_vertice(window_size, (x, y)), Some(vertice));
+        assert_eq!(map.pixel_to_vertice(window_size, (x + 10.0, y - 10.0)), Some(vertice));
+    }
+    assert_eq!(map.pixel_to_vertice(window_size, (-100.0, 10.0)), None);
+    assert_eq!(map.pixel_to_vertice(window_size, (10.0, 700.0)), None);
+    assert_eq!(map.pixel_to_vertice((0, 0), (10.0, 10.0)), None);
+}
+
+#[test]
+fn test_neighbour_best_move() {
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    map.set_cost_model(CostModel::Uniform);
+    let center = VerticeLoc::new(1, 1);
+    // A uniform field is one big tie, settled by the lowest coordinate
+    assert_eq!(map.neighbour_best_move(center, &[]), Some(VerticeLoc::new(0, 0)));
+
+    map.set_neighbour_order(NeighbourOrder::Shuffled);
+    map.deposit_pheromone(&[center, VerticeLoc::new(2, 1)], 1.0).unwrap();
+    assert!((0..20).all(|_| map.neighbour_best_move(center, &[]) == Some(VerticeLoc::new(2, 1))));
+    assert_eq!(map.neighbour_best_move(center, &[VerticeLoc::new(2, 1)]), Some(VerticeLoc::new(0, 0)));
+
+    let all: Vec<VerticeLoc> = map.get_neighbours(center);
+    assert_eq!(map.neighbour_best_move(center, &all), None);
+}
+
+#[test]
+fn test_cost_history_capacity() {
+    let mut map = ACOMap::try_new(6, 6, 0.1).unwrap();
+    map.set_iterations(30);
+    map.set_record_history(true);
+    map.set_history_capacity(10);
+    map.find_path(VerticeLoc::new(0, 0), VerticeLoc::new(5, 4));
+    let history = map.cost_history();
+    assert_eq!(history.len(), 10);
+    assert!(history.windows(2).all(|costs| costs[1] <= costs[0]));
+
+    map.set_history_capacity(4);
+    assert_eq!(map.cost_history(), history[6..].to_vec());
+}
+
+#[test]
+fn test_merge_pheromone() {
+    let path = [VerticeLoc::new(0, 0), VerticeLoc::new(1, 1), VerticeLoc::new(2, 1)];
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    map.deposit_pheromone(&path, 3.0).unwrap();
+    let copy = map.clone();
+    for weight in [0.0, 0.3, 1.0] {
+        map.merge_pheromone(&copy, weight).unwrap();
+        assert!(map.pheromone_graph == copy.pheromone_graph);
+    }
+
+    // 4.0 on the path against 1.0 everywhere in the fresh map
+    let fresh = ACOMap::try_new(3, 3, 0.5).unwrap();
+    map.merge_pheromone(&fresh, 0.25).unwrap();
+    assert!((map.pheromone_graph.get_edg_value(path[0], path[1]) - 3.25).abs() < 1e-6);
+    assert!((map.pheromone_graph.get_edg_value(path[0], VerticeLoc::new(1, 0)) - 1.0).abs() < 1e-6);
+
+    let mut four = ACOMap::try_new(3, 3, 0.5).unwrap();
+    four.set_connectivity(Connectivity::Four);
+    assert_eq!(map.merge_pheromone(&four, 0.5), Err(AcoError::MapMismatch));
+    assert_eq!(map.merge_pheromone(&ACOMap::try_new(3, 4, 0.5).unwrap(), 0.5), Err(AcoError::MapMismatch));
+}
+
+#[test]
+fn test_max_pheromone_caps_deposits() {
+    let mut map = ACOMap::try_new(3, 3, 0.1).unwrap();
+    let edge = [VerticeLoc::new(0, 0), VerticeLoc::new(1, 1)];
+    (0..100).for_each(|_| map.deposit_pheromone(&edge, 1e37).unwrap());
+    let pheromone = map.pheromone_graph.get_edg_value(edge[0], edge[1]);
+    assert!(pheromone.is_finite());
+    assert_eq!(pheromone, DEFAULT_MAX_PHEROMONE);
+    assert_eq!(map.validate_field(), Ok(()));
+
+    assert_eq!(map.set_max_pheromone(f32::INFINITY), Err(AcoError::InvalidMaxPheromone(f32::INFINITY)));
+    map.set_max_pheromone(10.0).unwrap();
+    map.set_online_deposit(Some(OnlineDeposit {per_step: 100.0}));
+    map.deposit_online(VerticeLoc::new(2, 2), VerticeLoc::new(1, 1)).unwrap();
+    assert_eq!(map.pheromone_graph.get_edg_value(VerticeLoc::new(2, 2), VerticeLoc::new(1, 1)), 10.0);
+}
+
+#[test]
+fn test_solve_with_timeout_and_callback() {
+    let mut map = ACOMap::try_new(2, 1, 0.5).unwrap();
+    map.set_record_history(true);
+    let (start, goal) = (VerticeLoc::new(0, 0), VerticeLoc::new(1, 0));
+
+    // However small the budget, one iteration is run
+    let result = map.solve_with_timeout_and_callback(start, goal, std::time::Duration::ZERO, |_| ());
+    assert_eq!(result.map(|result| result.cost), Some(1.0));
+    assert_eq!(map.cost_history().len(), 1);
+
+    let mut map = ACOMap::try_new(8, 8, 0.1).unwrap();
+    map.set_obstacle(VerticeLoc::new(3, 3)).unwrap();
+    assert!(map.solve_with_timeout_and_callback(start, VerticeLoc::new(3, 3), std::time::Duration::ZERO, |_| ()).is_none());
+}
+
+#[test]
+fn test_solve_with_iterations_and_callback() {
+    let mut map = ACOMap::try_new(8, 8, 0.1).unwrap();
+    map.set_seed(Some(3));
+    map.set_obstacle(VerticeLoc::new(3, 3)).unwrap();
+    let (start, goal) = (VerticeLoc::new(0, 0), VerticeLoc::new(7, 5));
+    let mut reported = Vec::new();
+    let result = map.solve_with_iterations_and_callback(start, goal, 50, |best| reported.push(best.cost)).unwrap();
+
+    assert!(!reported.is_empty());
+    assert_eq!(reported.last(), Some(&result.cost));
+    assert!(result.path.is_valid(&map));
+    assert_eq!(result.path.first(), Some(&start));
+    assert_eq!(result.path.last(), Some(&goal));
+}
+
+#[test]
+fn test_solve_needs_agreement_to_converge() {
+    use crate::colony::CONVERGED_AFTER;
+
+    // A lone ant reaching the goal is no sign of convergence, the whole budget is used
+    let mut map = ACOMap::try_new(8, 8, 0.1).unwrap();
+    map.set_seed(Some(3));
+    map.set_ant_count(1);
+    map.set_record_history(true);
+    let (start, goal) = (VerticeLoc::new(0, 0), VerticeLoc::new(7, 5));
+    assert!(map.solve_with_iterations_and_callback(start, goal, 40, |_| ()).is_some());
+    assert_eq!(map.cost_history().len(), 40);
+
+    // With a single possible path every ant agrees, the run stops as soon as the best path held
+    let mut map = ACOMap::try_new(2, 1, 0.5).unwrap();
+    map.set_record_history(true);
+    let result = map.solve_with_iterations_and_callback(VerticeLoc::new(0, 0), VerticeLoc::new(1, 0), 1000, |_| ());
+    assert_eq!(result.map(|result| result.cost), Some(1.0));
+    assert_eq!(map.cost_history().len(), CONVERGED_AFTER + 1);
+}
+
+#[test]
+fn test_neighbour_count_map() {
+    let mut map = ACOMap::try_new(4, 3, 0.5).unwrap();
+    let open = map.neighbour_count_map();
+    assert_eq!(open.len(), 12);
+    assert_eq!(open[0], 3);
+    assert_eq!(open[1 + 4], 8);
+
+    let obstacle = VerticeLoc::new(1, 1);
+    map.set_obstacle(obstacle).unwrap();
+    let degrees = map.neighbour_count_map();
+    for idx in 0..map.vertex_count() {
+        let vertice = map.pheromone_graph.vertice(idx);
+        let expected = if vertice == obstacle {
+            0
+        } else if map.is_adjacent(vertice, obstacle) {
+            open[idx] - 1
+        } else {
+            open[idx]
+        };
+        assert_eq!(degrees[idx], expected, "{:?}", vertice);
+        if vertice != obstacle {
+            assert_eq!(degrees[idx] as usize, map.neighbour_count(vertice));
+        }
+    }
+}
+
+#[test]
+fn test_find_path_any_goal() {
+    let mut map = ACOMap::try_new(10, 10, 0.1).unwrap();
+    map.set_seed(Some(4));
+    let start = VerticeLoc::new(0, 0);
+    let (far, near) = (VerticeLoc::new(9, 9), VerticeLoc::new(3, 1));
+    let result = map.find_path_any_goal(start, &[far, near]).unwrap();
+    assert_eq!(result.path.first(), Some(&start));
+    assert_eq!(result.path.last(), Some(&near));
+    assert!(result.cost < map.octile_distance(start, far));
+    assert!(map.alternative_goals.is_empty());
+
+    map.set_obstacle(near).unwrap();
+    assert_eq!(map.find_path_any_goal(start, &[near, far]).unwrap().path.last(), Some(&far));
+    assert!(map.find_path_any_goal(start, &[near]).is_none());
+}
+
+#[test]
+fn test_perturb_pheromone() {
+    let mut map = ACOMap::try_new(6, 6, 0.1).unwrap();
+    map.set_max_pheromone(2.0).unwrap();
+    map.deposit_pheromone(&[VerticeLoc::new(0, 0), VerticeLoc::new(1, 0), VerticeLoc::new(2, 0)], 0.9).unwrap();
+    let before = map.clone();
+    let magnitude = 0.5;
+    map.perturb_pheromone(magnitude, &mut StdRng::seed_from_u64(7));
+
+    let mut changed = false;
+    for (v0, v1) in map.edges() {
+        let (old, new) = (before.pheromone_graph.get_edg_value(v0, v1), map.pheromone_graph.get_edg_value(v0, v1));
+        assert!((new - old).abs() <= magnitude + 1e-6);
+        assert!((MIN_PHEROMONE..=2.0).contains(&new));
+        changed |= new != old;
+    }
+    assert!(changed);
+
+    let unchanged = map.clone();
+    map.perturb_pheromone(0.0, &mut StdRng::seed_from_u64(7));
+    assert!(map.pheromone_graph == unchanged.pheromone_graph);
+}
+
+#[test]
+fn test_graph_edges_are_symmetric() {
+    let mut graph = ACOGraph::new(3, 3);
+    let (v0, v1) = (VerticeLoc::new(0, 0), VerticeLoc::new(1, 1));
+    graph.set_edg_value(v1, v0, 3.0);
+    assert_eq!(graph.get_edg_value(v0, v1), 3.0);
+    assert_eq!(graph.get_edg_value(v1, v0), 3.0);
+
+    // An ant walking back over a trail finds what was laid on the way out
+    let mut map = ACOMap::try_new(3, 3, 0.5).unwrap();
+    map.deposit_pheromone(&[v0, v1], 2.0).unwrap();
+    assert_eq!(map.pheromone_graph.get_edg_value(v1, v0), map.pheromone_graph.get_edg_value(v0, v1));
+}
+
+#[test]
+fn test_graph_fill_and_scale() {
+    let mut graph = ACOGraph::new(3, 3);
+    let (v0, v1, v2) = (VerticeLoc::new(0, 0), VerticeLoc::new(1, 1), VerticeLoc::new(2, 1));
+    graph.fill(2.0);
+    assert_eq!(graph.get_edg_value(v0, v1), 2.0);
+    assert_eq!(graph.get_edg_value(v2, v1), 2.0);
+
+    graph.set_edg_value(v1, v2, 4.0);
+    graph.scale(0.5);
+    assert_eq!(graph.get_edg_value(v0, v1), 1.0);
+    assert_eq!(graph.get_edg_value(v2, v1), 2.0);
+
+    graph.scale(0.0);
+    assert_eq!(graph.get_edg_value(v0, v1), 0.0);
+    assert_eq!(graph.get_edg_value(v1, v2), 0.0);
+}
+
+#[test]
+fn test_public_api_rejects_out_of_bounds_vertices() {
+    let mut map = ACOMap::try_new(4, 3, 0.1).unwrap();
+    let (inside, outside) = (VerticeLoc::new(3, 0), VerticeLoc::new(4, 0));
+    let before = map.clone();
+
+    // x = 4 would alias onto the next row if it reached the index computation
+    assert_eq!(map.get_next_vertice_with_exclusions(outside, &Vec::new()), Err(AcoError::OutOfBounds));
+    assert_eq!(map.deposit_pheromone(&[inside, outside], 1.0), Err(AcoError::OutOfBounds));
+    assert!(map.pheromone_graph == before.pheromone_graph);
+    assert_eq!(map.set_obstacle(outside), Err(AcoError::OutOfBounds));
+    assert_eq!(map.obstacle_count(), 0);
+
+    let mut ant = AntState::new(outside);
+    assert_eq!(map.step_ant(&mut ant, 10), Err(AcoError::OutOfBounds));
+    assert_eq!(ant.current, outside);
+
+    assert!(map.astar(inside, outside).is_none());
+    assert!(map.astar(outside, inside).is_none());
+    assert!(map.find_path_from(&[(outside, 1.0)], inside).is_none());
+    assert!(map.find_path_from(&[(VerticeLoc::new(0, 0), 1.0), (outside, 1.0)], inside).is_none());
+    assert!(map.find_path_from(&[(VerticeLoc::new(0, 0), 1.0)], outside).is_none());
+
+    assert_eq!(map.clear_obstacle(outside), Err(AcoError::OutOfBounds));
+    assert_eq!(map.forbid_edge(inside, outside), Err(AcoError::OutOfBounds));
+    assert_eq!(map.allow_edge(outside, inside), Err(AcoError::OutOfBounds));
+    assert_eq!(map.set_goal(Some(outside)), Err(AcoError::OutOfBounds));
+    assert_eq!(map.set_start(Some(outside)), Err(AcoError::OutOfBounds));
+    assert_eq!(map.deposit_source(0, &[inside, outside], 1.0), Err(AcoError::OutOfBounds));
+    assert_eq!((map.goal(), map.start()), (None, None));
+    assert!(map.source_pheromone.is_empty());
+    assert!(map.find_path_moving_goal(VerticeLoc::new(0, 0), |_| outside, 3).is_none());
+    assert!(map.find_path_moving_goal(VerticeLoc::new(0, 0), |iteration| if iteration < 2 { inside } else { outside }, 3).is_none());
+    assert_eq!(map.goal(), None);
+    assert!(map.edges_of(outside).is_empty());
 }
@@ -0,0 +1,115 @@
+use crate::aco::VerticeLoc;
+
+/// Maximum number of dead end vertices an ant remembers
+const MAX_EXCLUSIONS: usize = 150;
+
+/// A single ant wandering over the map, backtracking out of dead ends
+pub struct AntState {
+    pub start: VerticeLoc,
+    pub current: VerticeLoc,
+    /// Every vertice from start up to and including current, oldest first
+    pub path: Vec<VerticeLoc>,
+    pub exclusions: Vec<VerticeLoc>,
+    /// Vertice the ant is heading for, it stops once there
+    pub goal: Option<VerticeLoc>,
+    /// The ant reached its goal, ran into a dead end or walked too far
+    pub done: bool
+}
+
+/// How an ant weighs pheromone against the heuristic when picking its next move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AntProfile {
+    /// Exponent applied to the pheromone on an edge
+    pub alpha: f32,
+    /// Exponent applied to the heuristic desirability of an edge
+    pub beta: f32,
+    /// Probability of greedily taking the most attractive move instead of sampling one
+    pub q0: f32
+}
+
+impl Default for AntProfile {
+    fn default() -> Self {
+        AntProfile {alpha: 1.0, beta: 1.0, q0: 0.0}
+    }
+}
+
+/// What happened during a call to ACOMap::step_ant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Moved(VerticeLoc),
+    /// The ant backtracked all the way to its start without finding a way forward
+    /// and was put back there with a clean slate
+    Reset,
+    /// The backtracking budget ran out before the ant could move, it was put back at its start
+    Stuck
+}
+
+impl AntState {
+    pub fn new(start: VerticeLoc) -> Self {
+        AntState {start, current: start, path: vec![start], exclusions: Vec::new(), goal: None, done: false}
+    }
+
+    #[allow(dead_code)]
+    pub fn with_goal(start: VerticeLoc, goal: VerticeLoc) -> Self {
+        AntState {goal: Some(goal), ..AntState::new(start)}
+    }
+
+    /// Put the ant back at its start with a fresh path and no remembered dead ends
+    pub fn reset(&mut self) {
+        self.current = self.start;
+        self.path = vec![self.start];
+        self.exclusions.clear();
+        self.done = false;
+    }
+
+    /// Remember current as a dead end and step back along the path,
+    /// returns false if the ant is already back at its start
+    pub fn backtrack(&mut self) -> bool {
+        if self.path.len() < 2 {
+            return false;
+        }
+        if self.exclusions.len() > MAX_EXCLUSIONS {
+            self.exclusions.remove(0);
+        }
+        self.exclusions.push(self.current);
+
+        self.path.pop();
+        self.current = *self.path.last().expect("The start is never popped");
+        true
+    }
+
+    pub fn advance(&mut self, next: VerticeLoc) {
+        self.path.push(next);
+        self.current = next;
+    }
+
+    /// Vertices the ant must not step onto, its own path and remembered dead ends
+    pub fn visited(&self) -> Vec<VerticeLoc> {
+        [self.path.as_slice(), self.exclusions.as_slice()].concat()
+    }
+
+    /// The full route walked so far, from the start up to and including current.
+    /// Never empty, even after backtracking all the way to the start, so it is safe to draw.
+    pub fn route(&self) -> Vec<VerticeLoc> {
+        self.path.clone()
+    }
+}
+
+#[test]
+fn test_route_survives_backtracking_to_empty_path() {
+    let start = VerticeLoc::new(2, 2);
+    let mut ant = AntState::new(start);
+    ant.advance(VerticeLoc::new(3, 2));
+    assert_eq!(ant.route(), vec![start, VerticeLoc::new(3, 2)]);
+    assert!(ant.backtrack());
+    assert_eq!(ant.path, vec![start]);
+    assert_eq!(ant.exclusions, vec![VerticeLoc::new(3, 2)]);
+    assert!(!ant.backtrack());
+
+    // What the demo draws every frame: the route segments plus the ant itself
+    let route = ant.route();
+    assert_eq!(route, vec![start]);
+    assert_eq!(route.windows(2).count(), 0);
+    assert_eq!(route.last(), Some(&ant.current));
+    assert_eq!(ant.current, start);
+}
@@ -0,0 +1,394 @@
+use std::collections::HashSet;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::aco::{ACOMap, PathResult, Reinforcement, VerticeLoc};
+use crate::error::AcoError;
+use crate::observer::{NoopObserver, SolverObserver};
+use crate::roulette::RouletteSubjects;
+
+/// Iterations the best path has to hold, with every successful ant agreeing on it,
+/// before the colony counts as converged
+pub const CONVERGED_AFTER: usize = 5;
+
+/// Bookkeeping of a run in progress
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColonyStats {
+    pub iterations: usize,
+    pub successful_ants: usize,
+    pub failed_ants: usize,
+    /// Number of times the pheromone was reset after stagnating
+    pub restarts: usize,
+    /// Ants released so far per entry of the map's ant profiles
+    pub ants_per_profile: Vec<usize>
+}
+
+/// A run in progress, the map holds the topology and pheromone while the colony
+/// owns everything that only lives for the duration of the run
+pub struct AntColony<'a> {
+    map: &'a mut ACOMap,
+    starts: RouletteSubjects<VerticeLoc>,
+    goal: VerticeLoc,
+    /// Goal the map had before the colony took it over, given back on drop
+    previous_goal: Option<VerticeLoc>,
+    /// The search bounds were set from the search margin and are lifted on drop
+    margin_bounds: bool,
+    best: Option<PathResult>,
+    converged: bool,
+    /// Iterations in a row without a better path, unlike stagnant_iterations not reset by a restart
+    unchanged_iterations: usize,
+    stagnant_iterations: usize,
+    rng: StdRng,
+    stats: ColonyStats
+}
+
+/// Steps a colony and hands out the best path every time it improves, for anytime planning.
+/// Stops after the iteration budget, the caller may stop earlier once content.
+pub struct AnytimeSolutions<'a> {
+    colony: AntColony<'a>,
+    remaining_iterations: usize
+}
+
+impl<'a> AnytimeSolutions<'a> {
+    pub fn new(colony: AntColony<'a>, iterations: usize) -> Self {
+        AnytimeSolutions {colony, remaining_iterations: iterations}
+    }
+}
+
+impl Iterator for AnytimeSolutions<'_> {
+    type Item = PathResult;
+
+    fn next(&mut self) -> Option<PathResult> {
+        while self.remaining_iterations > 0 {
+            self.remaining_iterations -= 1;
+            if self.colony.step() {
+                return self.colony.best().cloned();
+            }
+        }
+        None
+    }
+}
+
+/// Random stream a colony draws from unless told otherwise. Island and simulation runs use
+/// their index as stream, so this one lies at the far end where no index reaches.
+pub const COLONY_STREAM: u64 = u64::MAX - 1;
+
+/// Independent random stream number stream derived from a master seed, from entropy without one
+pub fn stream_rng(seed: Option<u64>, stream: u64) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(split_mix(seed.wrapping_add(stream.wrapping_mul(0x9E37_79B9_7F4A_7C15)))),
+        None => StdRng::from_entropy()
+    }
+}
+
+/// SplitMix64 finalizer, spreads nearby seed and stream pairs over unrelated states
+fn split_mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl<'a> AntColony<'a> {
+    #[allow(dead_code)]
+    pub fn new(map: &'a mut ACOMap, start: VerticeLoc, goal: VerticeLoc) -> Self {
+        AntColony::with_starts(map, &[(start, 1.0)], goal)
+    }
+
+    /// Release every ant from one of starts, picked with a probability proportional to its weight.
+    /// The map is steered towards goal while the colony lives, its own goal is restored on drop.
+    /// The same goes for search bounds set from the search margin.
+    pub fn with_starts(map: &'a mut ACOMap, starts: &[(VerticeLoc, f32)], goal: VerticeLoc) -> Self {
+        let previous_goal = map.goal();
+        // A goal off the map is never reached, the ants are not steered towards one then
+        map.set_goal(Some(goal).filter(|goal| map.in_bounds(*goal))).expect("Only goals on the map are set");
+        let start_vertices: Vec<VerticeLoc> = starts.iter().map(|(start, _)| *start).collect();
+        let margin_bounds = map.apply_search_margin(&start_vertices, goal);
+        map.clear_source_pheromone();
+        map.clear_cost_history();
+        let rng = stream_rng(map.seed(), COLONY_STREAM);
+        AntColony {
+            map,
+            starts: RouletteSubjects(starts.iter().map(|(start, weight)| (*weight, *start)).collect()),
+            goal,
+            previous_goal,
+            margin_bounds,
+            best: None,
+            converged: false,
+            unchanged_iterations: 0,
+            stagnant_iterations: 0,
+            rng,
+            stats: ColonyStats::default()
+        }
+    }
+
+    /// Move the goal between iterations, the best path so far leads to the old goal and is dropped
+    pub fn set_goal(&mut self, goal: VerticeLoc) -> Result<(), AcoError> {
+        if goal != self.goal {
+            self.map.set_goal(Some(goal))?;
+            self.goal = goal;
+            self.best = None;
+            self.map.clear_cost_history();
+            if self.margin_bounds {
+                self.map.set_search_bounds(None);
+            }
+            let starts: Vec<VerticeLoc> = self.starts.0.iter().map(|(_, start)| *start).collect();
+            self.margin_bounds = self.map.apply_search_margin(&starts, goal);
+        }
+        Ok(())
+    }
+
+    /// Replace the random stream every start and move of this colony is drawn from
+    pub fn set_rng(&mut self, rng: StdRng) {
+        self.rng = rng;
+    }
+
+    /// Index into the starts of the start result was walked from
+    fn source_index(&self, result: &PathResult) -> Option<usize> {
+        let start = result.path.first()?;
+        self.starts.0.iter().position(|(_, source)| source == start)
+    }
+
+    fn sample_start(&mut self) -> Option<VerticeLoc> {
+        self.starts.sample(&mut self.rng)
+    }
+
+    /// Run a single iteration: release the ants, evaporate and let every ant that
+    /// reached the goal deposit pheromone. Returns true if a new best path was found.
+    pub fn step(&mut self) -> bool {
+        self.step_observed(&mut NoopObserver)
+    }
+
+    /// Same as step but reports the progress to observer
+    pub fn step_observed(&mut self, observer: &mut dyn SolverObserver) -> bool {
+        let iteration = self.stats.iterations;
+        let starts: Vec<VerticeLoc> = (0..self.map.ant_count())
+            .filter_map(|_| self.sample_start())
+            .collect();
+        let results = match self.map.online_deposit() {
+            Some(_) => self.map.construct_paths_online(&starts, self.goal, &mut self.rng),
+            None => self.map.construct_paths(&starts, self.goal, &mut self.rng)
+        };
+        self.stats.ants_per_profile.resize(self.map.ant_profiles().len(), 0);
+        (0..starts.len()).for_each(|ant| self.stats.ants_per_profile[self.map.profile_index(ant)] += 1);
+        observer.on_paths_constructed(iteration, &results);
+        self.step_with_paths(results, observer)
+    }
+
+    /// Lay the online deposit of paths walked elsewhere, as the ants would have while walking
+    pub fn deposit_online(&mut self, results: &[PathResult]) -> Result<(), AcoError> {
+        results.iter()
+            .flat_map(|result| result.path.windows(2))
+            .try_for_each(|edge| self.map.deposit_online(edge[0], edge[1]))
+    }
+
+    /// Same as step_observed but with paths walked elsewhere, e.g. a recording, instead of
+    /// releasing ants. Everything after the paths are walked happens exactly as in a step,
+    /// the online deposit is left to the caller, see deposit_online. Paths that are not valid
+    /// on the map count as failed ants.
+    pub fn step_with_paths(&mut self, results: Vec<PathResult>, observer: &mut dyn SolverObserver) -> bool {
+        let results: Vec<PathResult> = results.into_iter().filter(|result| result.path.is_valid(self.map)).collect();
+        let iteration = self.stats.iterations;
+        self.stats.iterations += 1;
+        self.stats.successful_ants += results.len();
+        self.stats.failed_ants += self.map.ant_count().saturating_sub(results.len());
+
+        let iteration_best = results.iter()
+            .min_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap_or(std::cmp::Ordering::Equal));
+        let improved = match (iteration_best, &self.best) {
+            (Some(candidate), Some(best)) => candidate.cost < best.cost,
+            (Some(_), None) => true,
+            (None, _) => false
+        };
+        if improved {
+            self.best = iteration_best.cloned();
+        }
+        self.unchanged_iterations = if improved { 0 } else { self.unchanged_iterations + 1 };
+        self.map.record_best_cost(self.best.as_ref().map_or(f32::INFINITY, |best| best.cost));
+
+        self.map.decay_recent_usage();
+        results.iter()
+            .flat_map(|result| result.path.windows(2))
+            .for_each(|edge| self.map.record_move(edge[0], edge[1]));
+        self.map.evaporate();
+        let reinforced: Vec<&PathResult> = match self.map.reinforcement() {
+            Reinforcement::AllAnts => results.iter().collect(),
+            Reinforcement::IterationBest => iteration_best.into_iter().collect(),
+            Reinforcement::GlobalBest => self.best.iter().collect()
+        };
+        for result in reinforced {
+            self.map.deposit_pheromone_inverse_cost(result).expect("Only paths on the map are reinforced");
+            if let Some(source) = self.source_index(result).filter(|_| self.starts.len() > 1 && result.cost > 0.0) {
+                let amount = self.map.deposit_amount() / result.cost;
+                self.map.deposit_source(source, &result.path, amount).expect("Only paths on the map are reinforced");
+            }
+        }
+        let global_best_weight = self.map.global_best_weight();
+        if let Some(best) = self.best.as_ref().filter(|best| global_best_weight > 0.0 && best.cost > 0.0) {
+            self.map.deposit_pheromone(&best.path, global_best_weight / best.cost).expect("Only paths on the map are kept as best");
+        }
+        self.restart_if_stagnant(improved);
+
+        if improved {
+            observer.on_new_best(iteration, self.best.as_ref().unwrap());
+        }
+        let agreed = results.len() >= 2 && results.iter().all(|result| result.path == results[0].path);
+        if !self.converged && agreed && self.unchanged_iterations >= CONVERGED_AFTER {
+            self.converged = true;
+            observer.on_converged(iteration);
+        }
+        observer.on_iteration_complete(iteration, self.best.as_ref());
+        improved
+    }
+
+    /// Reset the pheromone and seed the best path once no improvement was made
+    /// for the configured number of iterations
+    fn restart_if_stagnant(&mut self, improved: bool) {
+        self.stagnant_iterations = if improved { 0 } else { self.stagnant_iterations + 1 };
+        let limit = match self.map.restart_on_stagnation() {
+            Some(limit) => limit,
+            None => return
+        };
+        if let Some(best) = self.best.as_ref().filter(|_| self.stagnant_iterations >= limit) {
+            self.map.reset_pheromones();
+            self.map.seed_from_path(best).expect("Only paths on the map are kept as best");
+            self.stagnant_iterations = 0;
+            self.stats.restarts += 1;
+        }
+    }
+
+    /// Take in a path found elsewhere: lay its trail and adopt it if it beats the best so far.
+    /// Fails without touching the colony if the path leaves the map.
+    pub fn seed(&mut self, result: &PathResult) -> Result<(), AcoError> {
+        self.map.seed_from_path(result)?;
+        let is_better = match &self.best {
+            Some(best) => result.cost < best.cost,
+            None => true
+        };
+        if is_better {
+            self.best = Some(result.clone());
+        }
+        Ok(())
+    }
+
+    /// Run a number of iterations and return the best path found so far
+    #[allow(dead_code)]
+    pub fn run(&mut self, iterations: usize) -> Option<&PathResult> {
+        self.run_observed(iterations, &mut NoopObserver)
+    }
+
+    /// Same as run but reports the progress to observer
+    pub fn run_observed(&mut self, iterations: usize, observer: &mut dyn SolverObserver) -> Option<&PathResult> {
+        for _ in 0..iterations {
+            self.step_observed(observer);
+        }
+        self.best()
+    }
+
+    pub fn best(&self) -> Option<&PathResult> {
+        self.best.as_ref()
+    }
+
+    /// At least two ants of some iteration reached the goal, all of them took the same path and
+    /// the best path had not improved for CONVERGED_AFTER iterations
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Edges of the best path so far for quick membership tests while drawing, empty without a best path
+    #[allow(dead_code)]
+    pub fn best_path_edges(&self) -> HashSet<(VerticeLoc, VerticeLoc)> {
+        self.best.as_ref().map(PathResult::edges).unwrap_or_default()
+    }
+
+    #[allow(dead_code)]
+    pub fn stats(&self) -> &ColonyStats {
+        &self.stats
+    }
+}
+
+impl Drop for AntColony<'_> {
+    fn drop(&mut self) {
+        self.map.set_goal(self.previous_goal).expect("The previous goal was on the map");
+        if self.margin_bounds {
+            self.map.set_search_bounds(None);
+        }
+    }
+}
+
+#[test]
+fn test_colony_step_and_best() {
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    let start = VerticeLoc::new(0, 0);
+    let goal = VerticeLoc::new(4, 4);
+    let mut colony = AntColony::new(&mut map, start, goal);
+    assert!(colony.best().is_none());
+
+    colony.step();
+    assert_eq!(colony.stats().iterations, 1);
+    assert_eq!(colony.stats().successful_ants + colony.stats().failed_ants, 10);
+
+    let best = colony.run(5).cloned().unwrap();
+    assert_eq!(colony.stats().iterations, 6);
+    assert_eq!(best.path.first(), Some(&start));
+    assert_eq!(best.path.last(), Some(&goal));
+    assert_eq!(colony.best(), Some(&best));
+}
+
+#[test]
+fn test_weighted_start_distribution() {
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    let a = VerticeLoc::new(0, 0);
+    let b = VerticeLoc::new(0, 4);
+    let mut colony = AntColony::with_starts(&mut map, &[(a, 3.0), (b, 1.0)], VerticeLoc::new(4, 2));
+
+    const ANTS: usize = 20000;
+    let from_a = (0..ANTS).filter(|_| colony.sample_start() == Some(a)).count();
+    let frq_a = from_a as f32 / ANTS as f32;
+    assert!((frq_a - 0.75).abs() < 0.02, "freq(a) = {}", frq_a);
+}
+
+#[test]
+fn test_every_profile_builds_ants() {
+    use crate::ant::AntProfile;
+
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    map.set_ant_count(10);
+    map.set_ant_profiles(vec![
+        AntProfile {alpha: 1.0, beta: 5.0, q0: 0.9},
+        AntProfile {alpha: 1.0, beta: 0.5, q0: 0.0}
+    ]);
+    let mut colony = AntColony::new(&mut map, VerticeLoc::new(0, 0), VerticeLoc::new(4, 4));
+    colony.step();
+    assert_eq!(colony.stats().ants_per_profile, vec![5, 5]);
+    colony.step();
+    assert_eq!(colony.stats().ants_per_profile, vec![10, 10]);
+    assert!(colony.best().is_some());
+}
+
+#[test]
+fn test_best_path_edges() {
+    let mut map = ACOMap::try_new(5, 5, 0.1).unwrap();
+    let mut colony = AntColony::new(&mut map, VerticeLoc::new(4, 4), VerticeLoc::new(0, 0));
+    assert!(colony.best_path_edges().is_empty());
+
+    let best = colony.run(5).cloned().unwrap();
+    let edges = colony.best_path_edges();
+    assert_eq!(edges.len(), best.path.len() - 1);
+    best.path.windows(2).for_each(|edge| {
+        assert!(edges.contains(&(edge[0].min(edge[1]), edge[0].max(edge[1]))));
+    });
+}
+
+#[test]
+fn test_stream_rng_streams_are_independent() {
+    use rand::Rng;
+
+    let first = |seed: u64, stream: u64| stream_rng(Some(seed), stream).gen::<u64>();
+    // Neighbouring seeds must not trade streams, as they did when both were xored together
+    assert_ne!(first(6, 1), first(7, 0));
+    assert_ne!(first(6, 0), first(7, 1));
+    assert_ne!(first(6, 0), first(6, COLONY_STREAM));
+    assert_eq!(first(6, 3), first(6, 3));
+}
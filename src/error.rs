@@ -0,0 +1,40 @@
+/// Errors reported by fallible ACOMap operations
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AcoError {
+    /// Width and height have to be non-zero
+    InvalidDimensions(usize, usize),
+    /// Evaporation rate has to be in [0, 1)
+    InvalidEvaporationRate(f32),
+    /// A vertice outside of the map was given
+    OutOfBounds,
+    /// An evaporation map has to hold one rate per vertice, the given one held this many
+    InvalidEvaporationMap(usize),
+    /// The deposit amount Q has to be finite and larger than 0
+    InvalidDepositAmount(f32),
+    /// The pheromone cap has to be finite and larger than the pheromone floor
+    InvalidMaxPheromone(f32),
+    /// Obstacle density has to be in [0, 1)
+    InvalidDensity(f32),
+    /// The start is an obstacle or has no traversable neighbours
+    StartIsolated,
+    /// No ant made it to the goal
+    NoPathFound,
+    /// The vertice can not be reached from the one before it
+    Unreachable(crate::aco::VerticeLoc),
+    /// A supplied pheromone edge joins vertices that are not neighbours, has a negative or
+    /// non-finite value or was given a different value in each direction
+    InvalidEdge(crate::aco::VerticeLoc, crate::aco::VerticeLoc),
+    /// The pheromone on the edge between the vertices is NaN or infinite
+    NonFinitePheromone(crate::aco::VerticeLoc, crate::aco::VerticeLoc),
+    /// The other map has different dimensions or connectivity
+    MapMismatch,
+    /// A window dimension is zero, so nothing can be drawn into it
+    InvalidWindowSize(usize, usize),
+    /// The map image could not be read
+    Image(String),
+    /// The image of this width and height has more pixels than the map can hold, see MAX_IMAGE_VERTICES
+    ImageTooLarge(usize, usize),
+    /// The pool of worker threads could not be built
+    ThreadPool(String)
+}
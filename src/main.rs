@@ -1,6 +1,16 @@
 mod aco;
 use aco::{ACOMap, VerticeLoc};
+mod ant;
+use ant::AntState;
+mod colony;
+mod error;
+mod observer;
+mod path;
+mod recorder;
+mod style;
+use style::RenderStyle;
 mod roulette;
+mod vertex;
 
 use std::time::{Instant, Duration};
 
@@ -9,6 +19,9 @@ use speedy2d::{Window, Graphics2D};
 use speedy2d::window::{WindowHelper, WindowHandler, MouseButton};
 use speedy2d::color::Color;
 
+/// Backtracks allowed per frame before the ant gives up and restarts
+const MAX_BACKTRACKS_PER_FRAME: usize = 1000;
+
 struct PointerStatus {
     position: (f32, f32),
     l_btn_pushed: bool,
@@ -21,6 +34,21 @@ impl PointerStatus {
     }
 }
 
+/// Block the vertice under the pointer while the left button is held and free it while the
+/// right one is, the trails around it are reset. Returns the vertice that changed.
+fn paint_obstacle(map: &mut ACOMap, window_size: (usize, usize), pointer: &PointerStatus) -> Option<VerticeLoc> {
+    let vertice = map.pixel_to_vertice(window_size, pointer.position)?;
+    if pointer.l_btn_pushed && !map.is_obstacle(vertice) {
+        map.set_obstacle(vertice).ok()?;
+    } else if pointer.r_btn_pushed && !pointer.l_btn_pushed && map.is_obstacle(vertice) {
+        map.clear_obstacle(vertice).ok()?;
+    } else {
+        return None;
+    }
+    map.reset_edges_of(vertice).ok()?;
+    Some(vertice)
+}
+
 struct WindowContext {
     pointer_status: PointerStatus,
     window_size: (usize, usize),
@@ -29,9 +57,8 @@ struct WindowContext {
     iterations: usize,
 
     aco_map: ACOMap,
-    curr_vert: VerticeLoc,
-    path: Vec<VerticeLoc>,
-    exclusions: Vec<VerticeLoc>
+    ant: AntState,
+    render_style: RenderStyle
 }
 
 impl WindowHandler for WindowContext {
@@ -47,45 +74,13 @@ impl WindowHandler for WindowContext {
             println!("Framerate: {}", avg_frame_rate);
         }
 
+        self.aco_map.render_grid(self.window_size, graphics, &self.render_style);
         self.aco_map.render(self.window_size, graphics);
-        // if self.iterations % 5 == 0 {
-            let mut got_next = false;
-            while got_next == false {
-                match self.aco_map.get_next_vertice_with_exclusions(
-                self.curr_vert, &[self.path.as_slice(), self.exclusions.as_slice()].concat()) {
-                    None => {
-                        if self.exclusions.len() > 150 {
-                            self.exclusions.remove(0);
-                        }
-                        self.exclusions.push(self.curr_vert);
-
-                        self.curr_vert = self.path.pop().unwrap();
-                        got_next = false;
-                    },
-                    Some(next_vertice) => {
-                        self.path.push(self.curr_vert);
-                        self.curr_vert = next_vertice;
-                        got_next = true;
-                    }
-                };
-            }
-        // }
-        self.path.windows(2).for_each(|points| {
-            graphics.draw_line(
-                self.aco_map.get_vertice_coordinates(self.window_size, points[0]), 
-                self.aco_map.get_vertice_coordinates(self.window_size, points[1]),
-                1.0, 
-                Color::GREEN
-            );
-        });
-        graphics.draw_line(
-            self.aco_map.get_vertice_coordinates(self.window_size, *self.path.last().unwrap()), 
-            self.aco_map.get_vertice_coordinates(self.window_size, self.curr_vert),
-            1.0, 
-            Color::GREEN
-        );
-        graphics.draw_circle(self.aco_map.get_vertice_coordinates(self.window_size, 
-            self.curr_vert), 4.0, Color::RED);
+        self.aco_map.step_ant(&mut self.ant, MAX_BACKTRACKS_PER_FRAME).expect("The demo ant starts on the map");
+        self.aco_map.render_path(self.window_size, graphics, &self.ant.route(), &self.render_style);
+        if let Ok(ant_position) = self.aco_map.get_vertice_coordinates(self.window_size, self.ant.current) {
+            graphics.draw_circle(ant_position, 4.0, Color::RED);
+        }
 
 
         // Store the time to be able to measure duration
@@ -98,6 +93,7 @@ impl WindowHandler for WindowContext {
 
     fn on_mouse_move(&mut self, _helper: &mut WindowHelper<()>, position: Vector2<f32>) {
         self.pointer_status.position = (position.x, position.y);
+        paint_obstacle(&mut self.aco_map, self.window_size, &self.pointer_status);
     }
 
     fn on_mouse_button_down(&mut self, _helper: &mut WindowHelper<()>, button: MouseButton) {
@@ -106,6 +102,7 @@ impl WindowHandler for WindowContext {
             MouseButton::Right => self.pointer_status.r_btn_pushed = true,
             _ => ()
         }
+        paint_obstacle(&mut self.aco_map, self.window_size, &self.pointer_status);
     }
 
     fn on_mouse_button_up(&mut self, _helper: &mut WindowHelper<()>, button: speedy2d::window::MouseButton) {
@@ -119,17 +116,42 @@ impl WindowHandler for WindowContext {
 
 fn main() {
     let window = Window::new_centered("ACO Pathfind Simulation", (1200, 1200)).unwrap();
-    let mut window_context = WindowContext {
+    let window_context = WindowContext {
         pointer_status: PointerStatus::new(),
         window_size: (1200, 1200),
         prev_time: Instant::now(),
         accumulated_duration: Duration::new(0, 0),
         iterations: 0,
-        aco_map: ACOMap::new(100, 100, 0.5).expect("Failed to generate ACO map..."),
-        curr_vert: (7, 7),
-        path: Vec::new(),
-        exclusions: Vec::new()
+        aco_map: ACOMap::try_new(100, 100, 0.5).expect("Failed to generate ACO map..."),
+        ant: AntState::new(VerticeLoc::new(7, 7)),
+        render_style: RenderStyle::default()
     };
-    window_context.path.push(window_context.curr_vert);
     window.run_loop(window_context);
 }
+
+#[test]
+fn test_paint_obstacle() {
+    let mut map = ACOMap::try_new(10, 10, 0.5).unwrap();
+    let window_size = (500, 500);
+    let vertice = VerticeLoc::new(3, 4);
+    let (x, y) = map.get_vertice_coordinates(window_size, vertice).unwrap();
+    map.deposit_pheromone(&[vertice, VerticeLoc::new(4, 4)], 2.0).unwrap();
+
+    let mut pointer = PointerStatus {position: (x, y), l_btn_pushed: false, r_btn_pushed: false};
+    assert_eq!(paint_obstacle(&mut map, window_size, &pointer), None);
+
+    pointer.l_btn_pushed = true;
+    assert_eq!(paint_obstacle(&mut map, window_size, &pointer), Some(vertice));
+    assert!(map.is_obstacle(vertice));
+    assert_eq!(map.path_pheromones(&[VerticeLoc::new(4, 4), vertice]), Ok(vec![1.0]));
+    // Dragging over a vertice that is already blocked changes nothing
+    assert_eq!(paint_obstacle(&mut map, window_size, &pointer), None);
+
+    pointer.l_btn_pushed = false;
+    pointer.r_btn_pushed = true;
+    assert_eq!(paint_obstacle(&mut map, window_size, &pointer), Some(vertice));
+    assert!(!map.is_obstacle(vertice));
+
+    pointer.position = (-50.0, -50.0);
+    assert_eq!(paint_obstacle(&mut map, window_size, &pointer), None);
+}
@@ -0,0 +1,81 @@
+use crate::aco::PathResult;
+
+/// Hooks into the progress of a search, every method defaults to doing nothing so an
+/// implementation only has to pick the events it cares about. Lets users forward solver
+/// events to whatever logging they use without the crate depending on one.
+pub trait SolverObserver {
+    /// Called after every iteration, with the best path found so far
+    fn on_iteration_complete(&mut self, _iteration: usize, _best: Option<&PathResult>) {}
+
+    /// Called whenever an iteration improves on the best path
+    fn on_new_best(&mut self, _iteration: usize, _best: &PathResult) {}
+
+    /// Called the first time the colony converged, see AntColony::converged
+    fn on_converged(&mut self, _iteration: usize) {}
+
+    /// Called when the islands of an island search exchange their best paths
+    fn on_migration(&mut self, _iteration: usize) {}
+
+    /// Called with the path of every ant that reached the goal, before any pheromone is updated
+    fn on_paths_constructed(&mut self, _iteration: usize, _results: &[PathResult]) {}
+}
+
+/// Observer that ignores every event
+pub struct NoopObserver;
+
+impl SolverObserver for NoopObserver {}
+
+/// Observer that hands every new best path to a closure
+pub struct NewBestCallback<F: FnMut(&PathResult)>(pub F);
+
+impl<F: FnMut(&PathResult)> SolverObserver for NewBestCallback<F> {
+    fn on_new_best(&mut self, _iteration: usize, best: &PathResult) {
+        (self.0)(best);
+    }
+}
+
+#[test]
+fn test_observer_callback_sequence() {
+    use crate::aco::{ACOMap, VerticeLoc};
+    use crate::colony::CONVERGED_AFTER;
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        IterationComplete(usize, Option<f32>),
+        NewBest(usize, f32),
+        Converged(usize)
+    }
+
+    struct Recorder(Vec<Event>);
+
+    impl SolverObserver for Recorder {
+        fn on_iteration_complete(&mut self, iteration: usize, best: Option<&PathResult>) {
+            self.0.push(Event::IterationComplete(iteration, best.map(|best| best.cost)));
+        }
+
+        fn on_new_best(&mut self, iteration: usize, best: &PathResult) {
+            self.0.push(Event::NewBest(iteration, best.cost));
+        }
+
+        fn on_converged(&mut self, iteration: usize) {
+            self.0.push(Event::Converged(iteration));
+        }
+    }
+
+    // A single edge leaves the ants no choice, the colony converges once the best path
+    // found in the first iteration has held for long enough
+    let mut map = ACOMap::try_new(2, 1, 0.5).unwrap();
+    map.set_iterations(CONVERGED_AFTER + 2);
+    let mut recorder = Recorder(Vec::new());
+    let result = map.find_path_observed(VerticeLoc::new(0, 0), VerticeLoc::new(1, 0), &mut recorder);
+
+    assert_eq!(result.map(|result| result.cost), Some(1.0));
+    let mut expected = vec![Event::NewBest(0, 1.0)];
+    for iteration in 0..CONVERGED_AFTER + 2 {
+        if iteration == CONVERGED_AFTER {
+            expected.push(Event::Converged(iteration));
+        }
+        expected.push(Event::IterationComplete(iteration, Some(1.0)));
+    }
+    assert_eq!(recorder.0, expected);
+}
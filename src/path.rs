@@ -0,0 +1,103 @@
+use std::ops::Deref;
+
+use crate::aco::{ACOMap, VerticeLoc};
+
+/// Sequence of vertices walked from the first to the last one
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Path(pub Vec<VerticeLoc>);
+
+impl Path {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Path(Vec::new())
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Total cost of walking the path on map
+    #[allow(dead_code)]
+    pub fn cost(&self, map: &ACOMap) -> f32 {
+        map.path_cost(&self.0)
+    }
+
+    /// A path is valid if it is not empty and every step is a move map allows
+    #[allow(dead_code)]
+    pub fn is_valid(&self, map: &ACOMap) -> bool {
+        !self.0.is_empty() && self.0.windows(2).all(|edge| map.try_move(edge[0], edge[1]).is_ok())
+    }
+
+    #[allow(dead_code)]
+    pub fn contains(&self, vertice: VerticeLoc) -> bool {
+        self.0.contains(&vertice)
+    }
+}
+
+impl Deref for Path {
+    type Target = [VerticeLoc];
+
+    fn deref(&self) -> &[VerticeLoc] {
+        &self.0
+    }
+}
+
+impl From<Vec<VerticeLoc>> for Path {
+    fn from(vertices: Vec<VerticeLoc>) -> Self {
+        Path(vertices)
+    }
+}
+
+impl FromIterator<VerticeLoc> for Path {
+    fn from_iter<I: IntoIterator<Item = VerticeLoc>>(iter: I) -> Self {
+        Path(iter.into_iter().collect())
+    }
+}
+
+impl Extend<VerticeLoc> for Path {
+    fn extend<I: IntoIterator<Item = VerticeLoc>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Path {
+    type Item = VerticeLoc;
+    type IntoIter = std::vec::IntoIter<VerticeLoc>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Path {
+    type Item = &'a VerticeLoc;
+    type IntoIter = std::slice::Iter<'a, VerticeLoc>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl PartialEq<Vec<VerticeLoc>> for Path {
+    fn eq(&self, other: &Vec<VerticeLoc>) -> bool {
+        &self.0 == other
+    }
+}
+
+#[test]
+fn test_path_is_valid() {
+    let mut map = ACOMap::try_new(4, 4, 0.1).unwrap();
+    let good: Path = vec![VerticeLoc::new(0, 0), VerticeLoc::new(1, 1), VerticeLoc::new(1, 2)].into();
+    assert!(good.is_valid(&map));
+    assert_eq!(good.len(), 3);
+    assert!(good.contains(VerticeLoc::new(1, 1)));
+    assert!((good.cost(&map) - (std::f32::consts::SQRT_2 + 1.0)).abs() < 1e-6);
+
+    let broken: Path = vec![VerticeLoc::new(0, 0), VerticeLoc::new(2, 0)].into();
+    assert!(!broken.is_valid(&map));
+    assert!(!Path::new().is_valid(&map));
+
+    map.set_obstacle(VerticeLoc::new(1, 1)).unwrap();
+    assert!(!good.is_valid(&map));
+}
@@ -0,0 +1,99 @@
+use std::io::{BufRead, Write};
+
+use crate::aco::{PathResult, VerticeLoc};
+use crate::observer::SolverObserver;
+
+/// Records the path of every successful ant of every iteration so a run can be replayed
+/// with ACOMap::replay. Unlike a seed the recording does not depend on the order random
+/// numbers are drawn in, so it still replays after the solver changes.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunRecorder {
+    /// Paths walked in each iteration, in the order the ants were released
+    pub iterations: Vec<Vec<PathResult>>
+}
+
+impl RunRecorder {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        RunRecorder::default()
+    }
+
+    /// Write one line per iteration, the paths are separated by ';' and written as
+    /// cost followed by the x,y of every vertice
+    #[allow(dead_code)]
+    pub fn write<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        for results in &self.iterations {
+            let line: Vec<String> = results.iter()
+                .map(|result| {
+                    let vertices: Vec<String> = result.path.iter().map(|v| format!("{},{}", v.x(), v.y())).collect();
+                    format!("{} {}", result.cost, vertices.join(" "))
+                })
+                .collect();
+            writeln!(w, "{}", line.join(";"))?;
+        }
+        Ok(())
+    }
+
+    /// Read a recording written by write
+    #[allow(dead_code)]
+    pub fn read<R: BufRead>(r: R) -> std::io::Result<Self> {
+        let invalid = |line: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid recording line: {}", line));
+        let mut recorder = RunRecorder::new();
+        for line in r.lines() {
+            let line = line?;
+            let results = line.split(';')
+                .filter(|path| !path.is_empty())
+                .map(|path| {
+                    let mut fields = path.split(' ');
+                    let cost = fields.next().and_then(|cost| cost.parse().ok()).ok_or_else(|| invalid(&line))?;
+                    let path = fields
+                        .map(|vertice| match vertice.split_once(',') {
+                            Some((x, y)) => Ok(VerticeLoc::new(x.parse().map_err(|_| invalid(&line))?, y.parse().map_err(|_| invalid(&line))?)),
+                            None => Err(invalid(&line))
+                        })
+                        .collect::<std::io::Result<Vec<VerticeLoc>>>()?;
+                    Ok(PathResult {path: path.into(), cost})
+                })
+                .collect::<std::io::Result<Vec<PathResult>>>()?;
+            recorder.iterations.push(results);
+        }
+        Ok(recorder)
+    }
+}
+
+impl SolverObserver for RunRecorder {
+    fn on_paths_constructed(&mut self, _iteration: usize, results: &[PathResult]) {
+        self.iterations.push(results.to_vec());
+    }
+}
+
+#[test]
+fn test_replay_reproduces_run() {
+    use crate::aco::ACOMap;
+
+    let mut map = ACOMap::try_new(6, 6, 0.1).unwrap();
+    map.set_iterations(8);
+    map.set_obstacle(VerticeLoc::new(2, 2)).unwrap();
+    let mut replayed = map.clone();
+    let (start, goal) = (VerticeLoc::new(0, 0), VerticeLoc::new(5, 4));
+
+    let mut recorder = RunRecorder::new();
+    let best = map.find_path_observed(start, goal, &mut recorder);
+    assert_eq!(recorder.iterations.len(), 8);
+
+    let mut serialized = Vec::new();
+    recorder.write(&mut serialized).unwrap();
+    let recording = RunRecorder::read(serialized.as_slice()).unwrap();
+    assert_eq!(recording, recorder);
+    // Results compare by path only, so check the costs survived on their own
+    let costs = |recorder: &RunRecorder| recorder.iterations.iter().flatten().map(|result| result.cost).collect::<Vec<f32>>();
+    assert_eq!(costs(&recording), costs(&recorder));
+
+    let mut replay_recorder = RunRecorder::new();
+    assert_eq!(replayed.replay_observed(start, goal, &recording, &mut replay_recorder), best);
+    assert_eq!(replay_recorder, recorder);
+    assert_eq!(replayed.total_pheromone(), map.total_pheromone());
+
+    assert!(RunRecorder::read("x 0,0".as_bytes()).is_err());
+}
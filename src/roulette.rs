@@ -3,7 +3,7 @@ use rand::{thread_rng, Rng};
 pub struct RouletteSubjects<T> (pub Vec<(f32, T)>);
 
 impl<T> RouletteSubjects<T> 
-where T: Copy,
+where T: Clone,
 {
     #[allow(dead_code)]
     pub fn new() -> Self {
@@ -11,30 +11,115 @@ where T: Copy,
     }
 
     pub fn roulette(&mut self) -> Option<T> {
-        self.sort();
-        let mut probability_sum = 0.0;
-        self.iter_mut().for_each(|mut pair| {
-            probability_sum += pair.0;
-            pair.0 = probability_sum;
-        });
+        self.sample(&mut thread_rng())
+    }
 
-        let mut rng = thread_rng();
-        let random: f32 = rng.gen::<f32>() * probability_sum;
-        let mut previous = 0.0;
+    /// Pick a subject with a probability proportional to its weight without
+    /// touching the weights, so the same subjects can be sampled over and over.
+    /// A single pass sums the weights and a second one walks to the pick.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<T> {
+        self.sample_ref(rng).cloned()
+    }
 
+    /// Same as sample but hands out a reference to the picked subject instead of a clone.
+    /// The sums are accumulated in f64 so hundreds of small weights do not drift.
+    pub fn sample_ref<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&T> {
+        let probability_sum: f64 = self.0.iter().map(sampling_weight).sum();
+        if probability_sum.is_nan() || probability_sum <= 0.0 {
+            return None;
+        }
 
+        let random = rng.gen::<f64>() * probability_sum;
+        let mut cumulative = 0.0;
         for pair in &self.0 {
-            if random >= previous && random < pair.0 {
-                return Some((*pair).1);
-            } else {
-                previous = pair.0;
+            cumulative += sampling_weight(pair);
+            if random < cumulative {
+                return Some(&pair.1);
+            }
+        }
+
+        // Rounding can leave random just above the accumulated sum
+        self.0.iter().rev().find(|pair| sampling_weight(pair) > 0.0).map(|pair| &pair.1)
+    }
+
+    /// Draw count subjects at once. The f64 running sums of the weights are built a single
+    /// time and every draw binary searches them, so large sets of subjects stay cheap.
+    #[allow(dead_code)]
+    pub fn sample_many<R: Rng + ?Sized>(&self, rng: &mut R, count: usize) -> Vec<T> {
+        let mut running_sum = 0.0;
+        let running_sums: Vec<f64> = self.0.iter()
+            .map(|pair| {
+                running_sum += sampling_weight(pair);
+                running_sum
+            })
+            .collect();
+        if running_sum.is_nan() || running_sum <= 0.0 {
+            return Vec::new();
+        }
+
+        (0..count)
+            .map(|_| {
+                let random = rng.gen::<f64>() * running_sum;
+                // Rounding can leave random at the very end, the last subject with weight takes it
+                let picked = running_sums.partition_point(|sum| *sum <= random)
+                    .min(running_sums.partition_point(|sum| *sum < running_sum));
+                self.0[picked].1.clone()
+            })
+            .collect()
+    }
+
+    /// Subject with the largest weight, ties are broken at random so no neighbour order is favoured
+    pub fn best<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<T> {
+        use rand::seq::SliceRandom;
+        let max_weight = self.0.iter()
+            .map(|pair| pair.0)
+            .filter(|weight| !weight.is_nan())
+            .fold(f32::NEG_INFINITY, f32::max);
+        let maxima: Vec<&T> = self.0.iter().filter(|pair| pair.0 == max_weight).map(|pair| &pair.1).collect();
+        maxima.choose(rng).map(|subject| (*subject).clone())
+    }
+
+    /// Same as sample but every weight is given as its natural logarithm. The weights are
+    /// shifted by the largest one before exponentiating (log-sum-exp), so huge or tiny
+    /// weights neither overflow nor all round to zero.
+    #[allow(dead_code)]
+    pub fn roulette_logspace<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<T> {
+        let max_log_weight = self.0.iter()
+            .map(|pair| pair.0)
+            .filter(|log_weight| !log_weight.is_nan())
+            .fold(f32::NEG_INFINITY, f32::max);
+        if !max_log_weight.is_finite() {
+            return None;
+        }
+
+        let weights: Vec<f64> = self.0.iter()
+            .map(|pair| if pair.0.is_nan() { 0.0 } else { (f64::from(pair.0) - f64::from(max_log_weight)).exp() })
+            .collect();
+        let random = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        let mut cumulative = 0.0;
+        for (weight, pair) in weights.iter().zip(&self.0) {
+            cumulative += weight;
+            if random < cumulative {
+                return Some(pair.1.clone());
             }
         }
+        weights.iter().zip(&self.0).rev().find(|(weight, _)| **weight > 0.0).map(|(_, pair)| pair.1.clone())
+    }
 
-        None
+    /// Scale the weights so they sum up to 1.0, if they sum up to zero (or are not
+    /// finite) every subject gets the same weight instead
+    pub fn normalize(&mut self) {
+        let weight_sum: f32 = self.0.iter().map(|pair| pair.0).sum();
+        if weight_sum.is_finite() && weight_sum > 0.0 {
+            self.0.iter_mut().for_each(|pair| pair.0 /= weight_sum);
+        } else {
+            let uniform = 1.0 / self.0.len() as f32;
+            self.0.iter_mut().for_each(|pair| pair.0 = uniform);
+        }
     }
 
     #[inline(always)]
+    #[allow(dead_code)]
     fn sort(&mut self) {
         self.0.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
     }
@@ -51,9 +136,22 @@ where T: Copy,
     }
 
     #[inline(always)]
+    #[allow(dead_code)]
     pub fn iter_mut(&mut self) -> impl Iterator<Item=&mut (f32, T)> {
         self.0.iter_mut()
     }
+
+    /// Keep only the subjects for which f returns true, weights are left untouched
+    #[inline(always)]
+    #[allow(dead_code)]
+    pub fn retain<F: Fn(&T) -> bool>(&mut self, f: F) {
+        self.0.retain(|pair| f(&pair.1));
+    }
+}
+
+/// Weight a subject is drawn with by sample and sample_many, negative and NaN weights count as zero
+fn sampling_weight<T>(pair: &(f32, T)) -> f64 {
+    pair.0.max(0.0) as f64
 }
 
 #[test]
@@ -105,4 +203,201 @@ fn test_vertice_probabilities_roulette() {
     assert_eq!(frq_05.round() as u32, 5);
 
     println!("freq(0.2) = {}, freq(0.3) = {}, freq(0.5) = {}", frq_02.round() as u32, frq_03.round() as u32, frq_05.round() as u32);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_vertice_probabilities_sample() {
+    use rand::SeedableRng;
+
+    let mut probabilities = RouletteSubjects::new();
+    probabilities.push((0.5, (5, 0)));
+    probabilities.push((0.2, (2, 0)));
+    probabilities.push((0.3, (3, 0)));
+    let weights = probabilities.0.clone();
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+    let mut sample_counts = [0usize; 6];
+    let mut roulette_counts = [0usize; 6];
+
+    const ITERATIONS: usize = 100000;
+
+    (0..ITERATIONS).for_each(|_| {
+        if let Some(v) = probabilities.sample(&mut rng) {
+            sample_counts[v.0] += 1;
+        }
+    });
+    assert_eq!(probabilities.0, weights);
+
+    (0..ITERATIONS).for_each(|_| {
+        if let Some(v) = probabilities.roulette() {
+            roulette_counts[v.0] += 1;
+        }
+    });
+
+    [2, 3, 5].iter().for_each(|i| {
+        let frq_sample = sample_counts[*i] as f32 / ITERATIONS as f32;
+        let frq_roulette = roulette_counts[*i] as f32 / ITERATIONS as f32;
+        assert!((frq_sample - *i as f32 / 10.0).abs() < 0.01);
+        assert!((frq_sample - frq_roulette).abs() < 0.015);
+    });
+}
+
+#[test]
+fn test_roulette_non_copy_subjects() {
+    #[derive(Clone)]
+    struct Subject {
+        name: String
+    }
+
+    let mut subjects = RouletteSubjects::new();
+    subjects.push((0.0, Subject {name: String::from("never")}));
+    subjects.push((1.0, Subject {name: String::from("always")}));
+
+    let mut rng = thread_rng();
+    (0..1000).for_each(|_| {
+        assert_eq!(subjects.sample_ref(&mut rng).map(|subject| subject.name.as_str()), Some("always"));
+        assert_eq!(subjects.roulette().map(|subject| subject.name), Some(String::from("always")));
+    });
+}
+
+#[test]
+fn test_normalize() {
+    let mut subjects = RouletteSubjects::new();
+    subjects.push((2.0, 'a'));
+    subjects.push((6.0, 'b'));
+    subjects.normalize();
+    assert!((subjects.0.iter().map(|pair| pair.0).sum::<f32>() - 1.0).abs() < 1e-6);
+    assert!((subjects.0[1].0 - 0.75).abs() < 1e-6);
+
+    let mut zeros = RouletteSubjects::new();
+    (0..4).for_each(|i| zeros.push((0.0, i)));
+    zeros.normalize();
+    assert!((zeros.0.iter().map(|pair| pair.0).sum::<f32>() - 1.0).abs() < 1e-6);
+    assert!(zeros.0.iter().all(|pair| pair.0 == 0.25));
+    assert!(zeros.roulette().is_some());
+
+    let mut empty = RouletteSubjects::<char>::new();
+    empty.normalize();
+    assert_eq!(empty.len(), 0);
+}
+
+#[test]
+fn test_roulette_logspace_matches_roulette() {
+    use rand::SeedableRng;
+
+    let weights = [(0.2, 2usize), (0.3, 3), (0.5, 5)];
+    let mut plain = RouletteSubjects::new();
+    let mut logspace = RouletteSubjects::new();
+    for (weight, subject) in weights {
+        plain.push((weight, subject));
+        // An offset this large overflows exp, only the differences between log weights matter
+        logspace.push((f32::ln(weight) + 500.0, subject));
+    }
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(13);
+    let mut plain_counts = [0usize; 6];
+    let mut logspace_counts = [0usize; 6];
+    const ITERATIONS: usize = 100000;
+    (0..ITERATIONS).for_each(|_| {
+        plain_counts[plain.sample(&mut rng).unwrap()] += 1;
+        logspace_counts[logspace.roulette_logspace(&mut rng).unwrap()] += 1;
+    });
+
+    [2, 3, 5].iter().for_each(|i| {
+        let frq_plain = plain_counts[*i] as f32 / ITERATIONS as f32;
+        let frq_logspace = logspace_counts[*i] as f32 / ITERATIONS as f32;
+        assert!((frq_plain - frq_logspace).abs() < 0.015, "{}: {} vs {}", i, frq_plain, frq_logspace);
+    });
+
+    let mut impossible = RouletteSubjects::new();
+    impossible.push((f32::NEG_INFINITY, 1));
+    assert_eq!(impossible.roulette_logspace(&mut rng), None);
+}
+
+#[test]
+fn test_best_picks_among_maxima() {
+    use rand::SeedableRng;
+
+    let mut subjects = RouletteSubjects::new();
+    subjects.push((0.4, 0usize));
+    subjects.push((0.1, 1));
+    subjects.push((0.4, 2));
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(14);
+    let mut counts = [0usize; 3];
+    (0..1000).for_each(|_| counts[subjects.best(&mut rng).unwrap()] += 1);
+    assert_eq!(counts[1], 0);
+    assert!(counts[0] > 400 && counts[2] > 400);
+    assert_eq!(RouletteSubjects::<usize>::new().best(&mut rng), None);
+}
+
+#[test]
+fn test_retain() {
+    let mut subjects = RouletteSubjects::new();
+    (0..6).for_each(|i| subjects.push((i as f32, i)));
+    subjects.retain(|subject| subject % 2 == 0);
+    assert_eq!(subjects.len(), 3);
+    assert_eq!(subjects.0, vec![(0.0, 0), (2.0, 2), (4.0, 4)]);
+}
+
+#[test]
+fn test_sample_many_large_n_is_unbiased() {
+    use rand::SeedableRng;
+
+    const SUBJECTS: usize = 500;
+    const DRAWS: usize = 1000000;
+    let mut subjects = RouletteSubjects::new();
+    (0..SUBJECTS).for_each(|i| subjects.push((1.0 / SUBJECTS as f32, i)));
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let mut counts = vec![0usize; SUBJECTS];
+    subjects.sample_many(&mut rng, DRAWS).into_iter().for_each(|i| counts[i] += 1);
+
+    // 2000 expected draws each, the tolerance is a little over 5 standard deviations
+    let expected = DRAWS as f32 / SUBJECTS as f32;
+    counts.iter().enumerate().for_each(|(i, count)| {
+        assert!((*count as f32 - expected).abs() < 0.12 * expected, "subject {} drawn {} times", i, count);
+    });
+    // The last subject must not soak up the rounding left over at the end of the walk
+    let first_half: usize = counts[..SUBJECTS / 2].iter().sum();
+    assert!((first_half as f32 / DRAWS as f32 - 0.5).abs() < 0.005);
+}
+
+#[test]
+fn test_sample_many_matches_sample() {
+    use rand::SeedableRng;
+
+    let mut subjects = RouletteSubjects::new();
+    subjects.push((0.2, 0usize));
+    subjects.push((0.0, 1));
+    subjects.push((0.8, 2));
+    subjects.push((0.0, 3));
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(15);
+    let drawn = subjects.sample_many(&mut rng, 10000);
+    assert_eq!(drawn.len(), 10000);
+    assert!(drawn.iter().all(|i| *i == 0 || *i == 2));
+    let frq = drawn.iter().filter(|i| **i == 2).count() as f32 / 10000.0;
+    assert!((frq - 0.8).abs() < 0.02);
+    assert!(RouletteSubjects::<usize>::new().sample_many(&mut rng, 5).is_empty());
+}
+
+#[test]
+fn test_sampling_ignores_negative_weights() {
+    use rand::SeedableRng;
+
+    let mut subjects = RouletteSubjects::new();
+    subjects.push((-1.0, 0usize));
+    subjects.push((0.5, 1));
+    subjects.push((f32::NAN, 2));
+    subjects.push((0.5, 3));
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(4);
+    let sampled: Vec<usize> = (0..1000).map(|_| *subjects.sample_ref(&mut rng).unwrap()).collect();
+    let drawn = subjects.sample_many(&mut rng, 1000);
+    assert!(sampled.iter().chain(&drawn).all(|i| *i == 1 || *i == 3));
+
+    let negative = RouletteSubjects(vec![(-2.0, 0usize), (1.0, 1)]);
+    assert_eq!(negative.sample(&mut rng), Some(1));
+    assert_eq!(RouletteSubjects(vec![(-1.0, 0usize)]).sample(&mut rng), None);
+}
@@ -0,0 +1,66 @@
+use speedy2d::color::Color;
+
+/// What render_layers draws for the map itself
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderLayer {
+    Pheromone,
+    /// Heatmap of the cost of every cell
+    Cost,
+    /// The cost heatmap with the pheromone on top
+    Both
+}
+
+/// Appearance of the overlays drawn on top of the map
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderStyle {
+    /// Width of path lines in pixels, None scales it with the cell spacing
+    pub path_width: Option<f32>,
+    pub path_color: Color,
+    /// Draw faint lines between orthogonally adjacent vertices under everything else
+    pub draw_grid: bool,
+    pub grid_color: Color,
+    /// Write the coordinates next to every vertice, only done on small grids
+    pub show_labels: bool,
+    pub layer: RenderLayer
+}
+
+impl RenderStyle {
+    /// Path width used when none is configured, proportional to the distance between
+    /// neighbouring vertices so paths stay readable regardless of grid and window size
+    pub fn default_path_width(cell_spacing: f32) -> f32 {
+        (cell_spacing / 8.0).max(1.0)
+    }
+
+    pub fn path_width(&self, cell_spacing: f32) -> f32 {
+        self.path_width.unwrap_or_else(|| RenderStyle::default_path_width(cell_spacing))
+    }
+}
+
+impl Default for RenderStyle {
+    fn default() -> Self {
+        RenderStyle {
+            path_width: None,
+            path_color: Color::GREEN,
+            draw_grid: false,
+            grid_color: Color::from_rgba(0.0, 0.0, 0.0, 0.1),
+            show_labels: false,
+            layer: RenderLayer::Pheromone
+        }
+    }
+}
+
+#[test]
+fn test_default_path_width_scales_with_spacing() {
+    use crate::aco::ACOMap;
+
+    let map = ACOMap::try_new(10, 10, 0.5).unwrap();
+    let style = RenderStyle::default();
+    let small = style.path_width(map.cell_spacing((200, 200)));
+    let large = style.path_width(map.cell_spacing((2000, 2000)));
+    assert!(small >= 1.0);
+    assert!((large / small - 10.0).abs() < 1e-3);
+
+    let fixed = RenderStyle {path_width: Some(3.0), ..RenderStyle::default()};
+    assert_eq!(fixed.path_width(map.cell_spacing((2000, 2000))), 3.0);
+}
@@ -0,0 +1,78 @@
+/// A location on the grid, `x` is the column and `y` the row
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Vertex {
+    x: usize,
+    y: usize
+}
+
+impl Vertex {
+    pub fn new(x: usize, y: usize) -> Self {
+        Vertex {x, y}
+    }
+
+    #[inline(always)]
+    pub fn x(&self) -> usize {
+        self.x
+    }
+
+    #[inline(always)]
+    pub fn y(&self) -> usize {
+        self.y
+    }
+
+    /// Get the vertex displaced by (dx, dy), None if it would end up at a negative coordinate
+    pub fn offset(&self, dx: i32, dy: i32) -> Option<Vertex> {
+        let x = self.x as i64 + dx as i64;
+        let y = self.y as i64 + dy as i64;
+        if x < 0 || y < 0 {
+            return None;
+        }
+        Some(Vertex::new(x as usize, y as usize))
+    }
+
+    /// Get the (dx, dy) displacement going from self to other
+    pub fn delta(&self, other: Vertex) -> (i32, i32) {
+        (other.x as i32 - self.x as i32, other.y as i32 - self.y as i32)
+    }
+}
+
+impl From<(usize, usize)> for Vertex {
+    fn from(location: (usize, usize)) -> Self {
+        Vertex::new(location.0, location.1)
+    }
+}
+
+impl From<Vertex> for (usize, usize) {
+    fn from(vertex: Vertex) -> Self {
+        (vertex.x, vertex.y)
+    }
+}
+
+#[test]
+fn test_vertex_ordering() {
+    let mut vertices = vec![Vertex::new(1, 0), Vertex::new(0, 2), Vertex::new(0, 1), Vertex::new(1, 1)];
+    vertices.sort();
+    assert_eq!(vertices, vec![Vertex::new(0, 1), Vertex::new(0, 2), Vertex::new(1, 0), Vertex::new(1, 1)]);
+    assert!(Vertex::new(0, 5) < Vertex::new(1, 0));
+    assert_eq!(Vertex::new(3, 3).max(Vertex::new(3, 4)), Vertex::new(3, 4));
+}
+
+#[test]
+fn test_vertex_conversions() {
+    let vertex: Vertex = (3, 7).into();
+    assert_eq!(vertex.x(), 3);
+    assert_eq!(vertex.y(), 7);
+    assert_eq!(Vertex::from((3, 7)), vertex);
+
+    let location: (usize, usize) = vertex.into();
+    assert_eq!(location, (3, 7));
+}
+
+#[test]
+fn test_vertex_arithmetic() {
+    let vertex = Vertex::new(2, 0);
+    assert_eq!(vertex.offset(1, 1), Some(Vertex::new(3, 1)));
+    assert_eq!(vertex.offset(-2, 0), Some(Vertex::new(0, 0)));
+    assert_eq!(vertex.offset(0, -1), None);
+    assert_eq!(vertex.delta(Vertex::new(1, 3)), (-1, 3));
+}